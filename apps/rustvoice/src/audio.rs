@@ -6,18 +6,122 @@
 
 use anyhow::{anyhow, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{SampleFormat, SampleRate, Stream, StreamConfig};
+use cpal::{SampleFormat, Stream, StreamConfig};
 use crossbeam_channel::{bounded, Receiver, Sender};
 use parking_lot::Mutex;
 use rubato::{FftFixedIn, Resampler};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Writer for a 16kHz mono recording of the captured input
+type WavRecorder = hound::WavWriter<std::io::BufWriter<std::fs::File>>;
 
 /// Target sample rate for Whisper (16kHz)
 const WHISPER_SAMPLE_RATE: u32 = 16000;
 
-/// Audio chunk duration in milliseconds
-const CHUNK_DURATION_MS: u32 = 500;
+/// Default audio chunk duration in milliseconds
+pub const DEFAULT_CHUNK_MS: u32 = 500;
+
+/// Default number of input frames per resampler FFT chunk
+pub const DEFAULT_RESAMPLER_CHUNK: usize = 1024;
+
+/// RMS level above which input is treated as speech
+pub const SPEECH_RMS_THRESHOLD: f32 = 0.01;
+
+/// Which input channel(s) feed transcription
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChannelSelect {
+    #[default]
+    Mix,            // Average all channels
+    Channel(usize), // Use one channel only (0-based)
+}
+
+impl ChannelSelect {
+    /// Convert from the settings value (0 = mix, n = channel n counting from 1)
+    pub fn from_settings(value: usize) -> Self {
+        match value {
+            0 => ChannelSelect::Mix,
+            n => ChannelSelect::Channel(n - 1),
+        }
+    }
+
+    /// Reduce interleaved frames to mono. A channel the input doesn't have
+    /// falls back to mixing.
+    pub fn downmix(&self, data: &[f32], channels: usize) -> Vec<f32> {
+        if channels <= 1 {
+            return data.to_vec();
+        }
+        match *self {
+            ChannelSelect::Channel(ch) if ch < channels => {
+                data.chunks(channels).filter_map(|frame| frame.get(ch).copied()).collect()
+            }
+            _ => data.chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+                .collect(),
+        }
+    }
+}
+
+/// RMS level automatic gain control steers the input toward
+const AGC_TARGET_RMS: f32 = 0.1;
+
+/// Most the automatic gain control will amplify (about +26 dB)
+const AGC_MAX_GAIN: f32 = 20.0;
+
+/// Blocks quieter than this are background noise; AGC leaves its gain alone
+const AGC_NOISE_FLOOR: f32 = 0.002;
+
+/// Input gain applied to the mono signal before it is buffered: a fixed
+/// gain, optionally followed by automatic gain control toward a target RMS.
+/// Amplification is limited per block so peaks never exceed full scale;
+/// unity gain without AGC leaves the signal untouched.
+#[derive(Debug, Clone, Copy)]
+pub struct GainControl {
+    gain: f32,     // Fixed linear gain
+    agc: bool,
+    agc_gain: f32, // Current AGC gain, smoothed across blocks
+}
+
+impl Default for GainControl {
+    fn default() -> Self {
+        Self { gain: 1.0, agc: false, agc_gain: 1.0 }
+    }
+}
+
+impl GainControl {
+    pub fn new(gain_db: f32, agc: bool) -> Self {
+        Self { gain: 10f32.powf(gain_db / 20.0), agc, agc_gain: 1.0 }
+    }
+
+    /// Scale a block of samples in place
+    pub fn apply(&mut self, samples: &mut [f32]) {
+        if self.gain == 1.0 && !self.agc {
+            return;
+        }
+        let mut gain = self.gain;
+        if self.agc {
+            let level = rms(samples) * gain;
+            if level > AGC_NOISE_FLOOR {
+                let wanted = (AGC_TARGET_RMS / level).clamp(1.0 / AGC_MAX_GAIN, AGC_MAX_GAIN);
+                // Back off quickly when too loud, creep up slowly when too quiet
+                let rate = if wanted < self.agc_gain { 0.5 } else { 0.05 };
+                self.agc_gain += (wanted - self.agc_gain) * rate;
+            }
+            gain *= self.agc_gain;
+        }
+        // Clipping protection: never amplify the block's peak past full scale
+        let peak = samples.iter().fold(0.0f32, |m, &x| m.max(x.abs()));
+        if gain > 1.0 && peak > 0.0 {
+            gain = gain.min((0.99 / peak).max(1.0));
+        }
+        if gain != 1.0 {
+            for x in samples.iter_mut() {
+                *x = (*x * gain).clamp(-1.0, 1.0);
+            }
+        }
+    }
+}
 
 /// Audio capture handle
 pub struct AudioCapture {
@@ -27,6 +131,16 @@ pub struct AudioCapture {
     _audio_tx: Sender<Vec<f32>>,
     current_device_name: Option<String>,
     audio_level: Arc<AtomicU32>, // Audio level (0.0 to 1.0 stored as f32 bits)
+    peak_level: Arc<AtomicU32>,  // Decaying sample peak (0.0 to 1.0 stored as f32 bits)
+    last_speech: Arc<Mutex<Instant>>, // When input last exceeded the speech threshold
+    resampler_chunk: usize, // Input frames per resampler chunk (FFT size)
+    chunk_ms: u32,          // Duration of the chunks sent to the transcriber
+    recording_path: Option<std::path::PathBuf>, // Where the next session is saved as WAV
+    recorder: Arc<Mutex<Option<WavRecorder>>>,
+    channel_select: ChannelSelect,
+    gain: GainControl,
+    paused: Arc<AtomicBool>,      // Input is discarded while set
+    break_pending: Arc<AtomicBool>, // Paused, but the pre-pause audio isn't flushed yet
 }
 
 /// Get list of available input devices
@@ -63,19 +177,91 @@ impl AudioCapture {
             _audio_tx: audio_tx,
             current_device_name: None,
             audio_level: Arc::new(AtomicU32::new(0)),
+            peak_level: Arc::new(AtomicU32::new(0)),
+            last_speech: Arc::new(Mutex::new(Instant::now())),
+            resampler_chunk: DEFAULT_RESAMPLER_CHUNK,
+            chunk_ms: DEFAULT_CHUNK_MS,
+            recording_path: None,
+            recorder: Arc::new(Mutex::new(None)),
+            channel_select: ChannelSelect::Mix,
+            gain: GainControl::default(),
+            paused: Arc::new(AtomicBool::new(false)),
+            break_pending: Arc::new(AtomicBool::new(false)),
         })
     }
     
+    /// Set the input gain in dB and whether automatic gain control runs on
+    /// top of it; applies on the next start
+    pub fn set_gain(&mut self, gain_db: f32, agc: bool) {
+        self.gain = GainControl::new(gain_db, agc);
+    }
+    
+    /// Choose which input channel is transcribed; applies on the next start
+    pub fn set_channel_select(&mut self, channel_select: ChannelSelect) {
+        self.channel_select = channel_select;
+    }
+    
+    /// Set the resampler chunk size in input frames; applies on the next start.
+    /// Larger chunks cost less CPU but add latency.
+    pub fn set_resampler_chunk(&mut self, frames: usize) {
+        self.resampler_chunk = frames.max(64);
+    }
+    
+    /// Set how much audio each chunk sent to the transcriber holds (50-5000ms);
+    /// applies on the next start. Shorter chunks lower latency.
+    pub fn set_chunk_ms(&mut self, ms: u32) {
+        self.chunk_ms = ms.clamp(50, 5000);
+    }
+    
+    /// Save the 16kHz mono audio of the next session to a WAV file at `path`
+    /// (`None` to not record). The file is finalized by `stop()`.
+    pub fn set_recording_path(&mut self, path: Option<std::path::PathBuf>) {
+        self.recording_path = path;
+    }
+    
+    /// File the current session is being recorded to, if any
+    pub fn recording_path(&self) -> Option<&std::path::Path> {
+        self.recording_path.as_deref().filter(|_| self.recorder.lock().is_some())
+    }
+    
     /// Get current device name
     pub fn get_current_device(&self) -> Option<&str> {
         self.current_device_name.as_deref()
     }
     
-    /// Get current audio level (0.0 to 1.0)
+    /// Get current audio level (0.0 to 1.0) of the input before gain: RMS
+    /// with a short release so it doesn't flicker between callbacks
     pub fn get_audio_level(&self) -> f32 {
         f32::from_bits(self.audio_level.load(Ordering::Relaxed))
     }
     
+    /// Get the recent sample peak (0.0 to 1.0) before gain, decaying over
+    /// about a second
+    pub fn get_peak_level(&self) -> f32 {
+        f32::from_bits(self.peak_level.load(Ordering::Relaxed))
+    }
+    
+    /// Pause or resume the input without closing the stream, so resuming is
+    /// instant. On pausing, the audio collected so far is sent on, followed by
+    /// an empty chunk that tells the transcriber to finalize it.
+    pub fn set_paused(&self, paused: bool) {
+        if paused && !self.paused.swap(true, Ordering::Relaxed) {
+            self.break_pending.store(true, Ordering::Relaxed);
+        } else if !paused && self.paused.swap(false, Ordering::Relaxed) {
+            // Time spent paused doesn't count towards the silence auto-stop
+            *self.last_speech.lock() = Instant::now();
+        }
+    }
+    
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+    
+    /// Time elapsed since speech was last detected on the input
+    pub fn silence_duration(&self) -> Duration {
+        self.last_speech.lock().elapsed()
+    }
+    
     /// Start recording from a specific device by name (or default if None)
     pub fn start_with_device(&mut self, device_name: Option<&str>) -> Result<()> {
         if self.is_recording.load(Ordering::Relaxed) {
@@ -112,7 +298,7 @@ impl AudioCapture {
                    sample_rate, channels, config.sample_format());
         
         // Calculate buffer size for chunk duration
-        let samples_per_chunk = (WHISPER_SAMPLE_RATE * CHUNK_DURATION_MS / 1000) as usize;
+        let samples_per_chunk = (WHISPER_SAMPLE_RATE * self.chunk_ms / 1000) as usize;
         
         // Create resampler if needed
         let resampler = if sample_rate != WHISPER_SAMPLE_RATE {
@@ -120,7 +306,7 @@ impl AudioCapture {
                 FftFixedIn::<f32>::new(
                     sample_rate as usize,
                     WHISPER_SAMPLE_RATE as usize,
-                    1024,
+                    self.resampler_chunk,
                     2,
                     1, // Mono output
                 )?
@@ -129,74 +315,47 @@ impl AudioCapture {
             None
         };
         
-        let audio_tx = self._audio_tx.clone();
-        let is_recording = self.is_recording.clone();
-        let audio_level = self.audio_level.clone();
-        let buffer = Arc::new(Mutex::new(Vec::<f32>::with_capacity(samples_per_chunk * 2)));
-        
-        let buffer_clone = buffer.clone();
-        // Buffer for the Resampler (needs fixed chunk input, e.g., 1024)
-        let input_buffer = Arc::new(Mutex::new(Vec::<f32>::with_capacity(2048)));
-        let input_buffer_clone = input_buffer.clone();
-
-        let resampler_clone = resampler.clone();
-        let audio_level_clone = audio_level.clone();
+        if let ChannelSelect::Channel(ch) = self.channel_select {
+            if ch >= channels {
+                log::warn!("Input has {} channels, channel {} not available; mixing instead", channels, ch + 1);
+            }
+        }
         
-        let err_fn = |err| log::error!("Audio stream error: {}", err);
+        let ctx = StreamContext {
+            channels,
+            channel_select: self.channel_select,
+            gain: Arc::new(Mutex::new(self.gain)),
+            // Buffer for the Resampler (needs fixed chunk input)
+            input_buffer: Arc::new(Mutex::new(Vec::<f32>::with_capacity(self.resampler_chunk * 2))),
+            buffer: Arc::new(Mutex::new(Vec::<f32>::with_capacity(samples_per_chunk * 2))),
+            resampler,
+            audio_tx: self._audio_tx.clone(),
+            samples_per_chunk,
+            audio_level: self.audio_level.clone(),
+            peak_level: self.peak_level.clone(),
+            last_speech: self.last_speech.clone(),
+            recorder: self.recorder.clone(),
+            paused: self.paused.clone(),
+            break_pending: self.break_pending.clone(),
+        };
         
+        let stream_config: StreamConfig = config.clone().into();
+        *self.last_speech.lock() = Instant::now();
         let stream = match config.sample_format() {
-            SampleFormat::F32 => {
-                device.build_input_stream(
-                    &config.into(),
-                    move |data: &[f32], _: &_| {
-                        process_audio_data(
-                            data,
-                            channels,
-                            sample_rate,
-                            &input_buffer_clone, // New buffer for accumulating 1024 chunks
-                            &buffer_clone,
-                            &resampler_clone,
-                            &audio_tx,
-                            samples_per_chunk,
-                            &audio_level_clone,
-                        );
-                    },
-                    err_fn,
-                    None,
-                )?
-            }
-            SampleFormat::I16 => {
-                let buffer_clone = buffer.clone();
-                let input_buffer_clone = input_buffer.clone();
-                let resampler_clone = resampler.clone();
-                let audio_level_clone = audio_level.clone();
-                device.build_input_stream(
-                    &config.into(),
-                    move |data: &[i16], _: &_| {
-                        let float_data: Vec<f32> = data.iter()
-                            .map(|&s| s as f32 / i16::MAX as f32)
-                            .collect();
-                        process_audio_data(
-                            &float_data,
-                            channels,
-                            sample_rate,
-                            &input_buffer_clone,
-                            &buffer_clone,
-                            &resampler_clone,
-                            &audio_tx,
-                            samples_per_chunk,
-                            &audio_level_clone,
-                        );
-                    },
-                    err_fn,
-                    None,
-                )?
-            }
+            SampleFormat::F32 => build_stream::<f32>(&device, &stream_config, ctx)?,
+            SampleFormat::I16 => build_stream::<i16>(&device, &stream_config, ctx)?,
+            SampleFormat::I32 => build_stream::<i32>(&device, &stream_config, ctx)?,
+            SampleFormat::U16 => build_stream::<u16>(&device, &stream_config, ctx)?,
             sample_format => {
                 return Err(anyhow!("Unsupported sample format: {:?}", sample_format));
             }
         };
         
+        if let Some(path) = &self.recording_path {
+            *self.recorder.lock() = Some(create_recorder(path)?);
+            log::info!("Recording input to {:?}", path);
+        }
+        
         stream.play()?;
         self.stream = Some(stream);
         self.is_recording.store(true, Ordering::Relaxed);
@@ -215,6 +374,12 @@ impl AudioCapture {
         if let Some(stream) = self.stream.take() {
             drop(stream);
         }
+        if let Some(writer) = self.recorder.lock().take() {
+            // Writes the final header sizes
+            if let Err(e) = writer.finalize() {
+                log::error!("Failed to finalize recording: {}", e);
+            }
+        }
         self.is_recording.store(false, Ordering::Relaxed);
         log::info!("Audio capture stopped");
     }
@@ -236,33 +401,125 @@ impl Drop for AudioCapture {
     }
 }
 
-/// Process incoming audio data, resample to 16kHz mono, and send chunks
-fn process_audio_data(
-    data: &[f32],
+/// Raw device sample that can be normalized to f32 in [-1.0, 1.0]
+pub trait ToF32Sample: Copy {
+    fn to_f32_sample(self) -> f32;
+}
+
+impl ToF32Sample for f32 {
+    fn to_f32_sample(self) -> f32 {
+        self
+    }
+}
+
+impl ToF32Sample for i16 {
+    // Divide by 2^15 (not i16::MAX) so -32768 maps exactly to -1.0
+    fn to_f32_sample(self) -> f32 {
+        self as f32 / 32768.0
+    }
+}
+
+impl ToF32Sample for i32 {
+    fn to_f32_sample(self) -> f32 {
+        (self as f64 / 2_147_483_648.0) as f32
+    }
+}
+
+impl ToF32Sample for u16 {
+    // Unsigned samples are centred on 2^15
+    fn to_f32_sample(self) -> f32 {
+        (self as f32 - 32768.0) / 32768.0
+    }
+}
+
+/// Convert a buffer of raw device samples to normalized f32
+pub fn convert_samples<T: ToF32Sample>(data: &[T]) -> Vec<f32> {
+    data.iter().map(|&s| s.to_f32_sample()).collect()
+}
+
+/// State shared between the cpal callback and the processing pipeline
+#[derive(Clone)]
+struct StreamContext {
     channels: usize,
-    _sample_rate: u32,
-    input_buffer: &Arc<Mutex<Vec<f32>>>, // Accumulator for resampler input
-    buffer: &Arc<Mutex<Vec<f32>>>,       // Accumulator for Whisper chunks
-    resampler: &Option<Arc<Mutex<FftFixedIn<f32>>>>,
-    audio_tx: &Sender<Vec<f32>>,
+    channel_select: ChannelSelect,
+    gain: Arc<Mutex<GainControl>>,
+    input_buffer: Arc<Mutex<Vec<f32>>>, // Accumulator for resampler input
+    buffer: Arc<Mutex<Vec<f32>>>,       // Accumulator for Whisper chunks
+    resampler: Option<Arc<Mutex<FftFixedIn<f32>>>>,
+    audio_tx: Sender<Vec<f32>>,
     samples_per_chunk: usize,
-    audio_level: &Arc<AtomicU32>,
-) {
-    // Convert to mono by averaging channels
-    let mono: Vec<f32> = if channels > 1 {
-        data.chunks(channels)
-            .map(|chunk| chunk.iter().sum::<f32>() / channels as f32)
-            .collect()
-    } else {
-        data.to_vec()
-    };
+    audio_level: Arc<AtomicU32>,
+    peak_level: Arc<AtomicU32>,
+    recorder: Arc<Mutex<Option<WavRecorder>>>,
+    last_speech: Arc<Mutex<Instant>>,
+    paused: Arc<AtomicBool>,
+    break_pending: Arc<AtomicBool>,
+}
+
+/// Build an input stream for sample type `T`, converting to f32 before processing
+fn build_stream<T>(device: &cpal::Device, config: &StreamConfig, ctx: StreamContext) -> Result<Stream>
+where
+    T: ToF32Sample + cpal::SizedSample,
+{
+    let err_fn = |err| log::error!("Audio stream error: {}", err);
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _: &_| {
+            let float_data = convert_samples(data);
+            process_audio_data(&float_data, &ctx);
+        },
+        err_fn,
+        None,
+    )?;
+    Ok(stream)
+}
+
+/// Process incoming audio data, resample to 16kHz mono, and send chunks
+fn process_audio_data(data: &[f32], ctx: &StreamContext) {
+    let channels = ctx.channels;
+    let input_buffer = &ctx.input_buffer;
+    let buffer = &ctx.buffer;
+    let resampler = &ctx.resampler;
+    let samples_per_chunk = ctx.samples_per_chunk;
+    let audio_level = &ctx.audio_level;
+
+    if ctx.paused.load(Ordering::Relaxed) {
+        if ctx.break_pending.swap(false, Ordering::Relaxed) {
+            // Send the partial chunk so the last words before the pause aren't lost
+            let rest: Vec<f32> = buffer.lock().drain(..).collect();
+            if !rest.is_empty() {
+                send_chunk(rest, ctx);
+            }
+            input_buffer.lock().clear();
+            send_chunk(Vec::new(), ctx);
+        }
+        audio_level.store(0.0f32.to_bits(), Ordering::Relaxed);
+        ctx.peak_level.store(0.0f32.to_bits(), Ordering::Relaxed);
+        return;
+    }
+
+    // Convert to mono (average, or the selected channel)
+    let mut mono = ctx.channel_select.downmix(data, channels);
     
-    // Calculate RMS (Root Mean Square) for audio level indicator
+    // Calculate RMS (Root Mean Square) for audio level indicator.
+    // Updated on every callback, before any chunk is complete. The meter
+    // shows the input before gain, so clipping at the source stays visible.
     if !mono.is_empty() {
-        let rms: f32 = (mono.iter().map(|&x| x * x).sum::<f32>() / mono.len() as f32).sqrt();
         // Clamp to 0.0-1.0 range (audio is typically -1.0 to 1.0)
-        let level = rms.min(1.0).max(0.0);
+        let block = rms(&mono).clamp(0.0, 1.0);
+        // Rise at once, fall gradually
+        let previous = f32::from_bits(audio_level.load(Ordering::Relaxed));
+        let level = if block >= previous { block } else { previous * 0.85 + block * 0.15 };
         audio_level.store(level.to_bits(), Ordering::Relaxed);
+        let block_peak = mono.iter().fold(0.0f32, |m, &x| m.max(x.abs())).min(1.0);
+        let previous_peak = f32::from_bits(ctx.peak_level.load(Ordering::Relaxed));
+        ctx.peak_level.store(block_peak.max(previous_peak * 0.97).to_bits(), Ordering::Relaxed);
+    }
+    
+    ctx.gain.lock().apply(&mut mono);
+    // Speech is detected on what the transcriber will hear
+    if rms(&mono) > SPEECH_RMS_THRESHOLD {
+        *ctx.last_speech.lock() = Instant::now();
     }
     
     // Resample if necessary
@@ -273,10 +530,10 @@ fn process_audio_data(
             in_buf.extend(mono);
         } // Release input_buffer lock
         
-        // 2. Process in chunks of 1024 (FftFixedIn requirement)
-        let input_needed = 1024; // Fixed size for FftFixedIn
-        
+        // 2. Process in the fixed chunk size the resampler asks for
         loop {
+            let input_needed = resampler.lock().input_frames_next();
+            
             // Check if we have enough data (acquire and release lock quickly)
             let has_enough = {
                 let in_buf = input_buffer.lock();
@@ -322,15 +579,153 @@ fn process_audio_data(
     let mut buf = buffer.lock();
     if buf.len() >= samples_per_chunk {
         let chunk: Vec<f32> = buf.drain(..samples_per_chunk).collect();
-        log::debug!("Sending audio chunk, {} samples", chunk.len());
-        if audio_tx.try_send(chunk).is_err() {
-            log::warn!("Audio buffer full, dropping chunk");
+        drop(buf);
+        send_chunk(chunk, ctx);
+    }
+}
+
+/// Create a 16kHz mono float WAV file for recording the input, and its folder
+fn create_recorder(path: &std::path::Path) -> Result<WavRecorder> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: WHISPER_SAMPLE_RATE,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    Ok(hound::WavWriter::create(path, spec)?)
+}
+
+/// Append a chunk to a recording
+fn write_recording(writer: &mut WavRecorder, chunk: &[f32]) -> hound::Result<()> {
+    chunk.iter().try_for_each(|&s| writer.write_sample(s))
+}
+
+/// Record a 16kHz chunk and send it to the transcriber
+fn send_chunk(chunk: Vec<f32>, ctx: &StreamContext) {
+    log::debug!("Sending audio chunk, {} samples", chunk.len());
+    let mut recorder = ctx.recorder.lock();
+    if let Some(writer) = recorder.as_mut() {
+        if let Err(e) = write_recording(writer, &chunk) {
+            log::error!("Recording write failed, recording stopped: {}", e);
+            *recorder = None;
+        }
+    }
+    drop(recorder);
+    if ctx.audio_tx.try_send(chunk).is_err() {
+        log::warn!("Audio buffer full, dropping chunk");
+    }
+}
+
+/// Root mean square level of a block of samples
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|&x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// 30ms VAD frame at 16kHz
+const VAD_FRAME: usize = 480;
+
+/// Whether a block of 16kHz audio has energy above `threshold` (RMS) in
+/// enough of its 30ms frames (at least 10%) to be worth transcribing. A
+/// threshold of 0 counts every frame that isn't digital silence.
+pub fn contains_speech(samples: &[f32], threshold: f32) -> bool {
+    let frames = samples.len() / VAD_FRAME;
+    if frames == 0 {
+        return rms(samples) > threshold;
+    }
+    let voiced = samples
+        .chunks_exact(VAD_FRAME)
+        .filter(|frame| rms(frame) > threshold)
+        .count();
+    voiced * 10 >= frames
+}
+
+/// Number of samples of silence (30ms frames at or below `threshold`) at the
+/// end of a block of 16kHz audio, in whole frames. Louder audio earlier in
+/// the block doesn't matter.
+pub fn trailing_silence(samples: &[f32], threshold: f32) -> usize {
+    let silent_frames = samples
+        .rchunks_exact(VAD_FRAME)
+        .take_while(|frame| rms(frame) <= threshold)
+        .count();
+    silent_frames * VAD_FRAME
+}
+
+/// Scale a segment to a consistent level before feature extraction
+///
+/// `mode` is "peak" (loudest sample at -1 dBFS), "rms" (average level around
+/// -20 dBFS) or anything else for no change. Gain is capped so near-silent
+/// segments aren't blown up into loud noise.
+pub fn normalize_level(samples: &[f32], mode: &str) -> Vec<f32> {
+    const MAX_GAIN: f32 = 20.0;
+    let gain = match mode {
+        "peak" => {
+            let peak = samples.iter().fold(0.0f32, |m, &x| m.max(x.abs()));
+            if peak > 0.0 { 0.89 / peak } else { 1.0 }
+        }
+        "rms" => {
+            let level = rms(samples);
+            if level > 0.0 { 0.1 / level } else { 1.0 }
+        }
+        _ => 1.0,
+    }
+    .min(MAX_GAIN);
+    
+    samples.iter().map(|&x| (x * gain).clamp(-1.0, 1.0)).collect()
+}
+
+/// Get the duration of an audio file in seconds from its container metadata
+pub fn probe_duration(path: &std::path::Path) -> anyhow::Result<f64> {
+    use symphonia::core::codecs::CODEC_TYPE_NULL;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::probe::Hint;
+
+    let src = std::fs::File::open(path)?;
+    let mss = MediaSourceStream::new(Box::new(src), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &Default::default(), &Default::default())?;
+    let track = probed.format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| anyhow!("no supported audio tracks"))?;
+
+    let frames = track.codec_params.n_frames.ok_or_else(|| anyhow!("unknown length"))?;
+    let rate = track.codec_params.sample_rate.ok_or_else(|| anyhow!("unknown sample rate"))?;
+    Ok(frames as f64 / rate as f64)
+}
+
+/// Input frames per block when resampling a decoded file
+const FILE_RESAMPLER_CHUNK: usize = 8192;
+
+/// Reduce planar channels to mono, frame by frame: the selected channel,
+/// or the average when mixing (or when the file doesn't have that channel)
+fn downmix_planes(planes: &[&[f32]], channel_select: ChannelSelect) -> Vec<f32> {
+    if let ChannelSelect::Channel(ch) = channel_select {
+        if let Some(plane) = planes.get(ch) {
+            return plane.to_vec();
         }
     }
+    let frames = planes.iter().map(|p| p.len()).min().unwrap_or(0);
+    (0..frames)
+        .map(|i| planes.iter().map(|p| p[i]).sum::<f32>() / planes.len() as f32)
+        .collect()
 }
 
-/// Decode an audio file to 16kHz mono (F32) using Symphonia
-pub fn decode_audio_file(path: &std::path::Path) -> anyhow::Result<Vec<f32>> {
+/// Decode an audio file to 16kHz mono (F32) using Symphonia. Multi-channel
+/// files are reduced to mono per `channel_select`, like live input.
+pub fn decode_audio_file(path: &std::path::Path, channel_select: ChannelSelect) -> anyhow::Result<Vec<f32>> {
     use symphonia::core::audio::Signal;
     use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
     use symphonia::core::formats::FormatOptions;
@@ -389,9 +784,7 @@ pub fn decode_audio_file(path: &std::path::Path) -> anyhow::Result<Vec<f32>> {
                 } else {
                      let mut buf = symphonia::core::audio::AudioBuffer::<f32>::new(duration as u64, spec);
                      decoded.convert(&mut buf);
-                     let planes = buf.planes();
-                     let p0 = planes.planes()[0];
-                     samples.extend_from_slice(p0);
+                     samples.extend(downmix_planes(buf.planes().planes(), channel_select));
                 }
             }
             Err(e) => {
@@ -401,32 +794,217 @@ pub fn decode_audio_file(path: &std::path::Path) -> anyhow::Result<Vec<f32>> {
         }
     }
     
-    // Resample to 16000 Hz if needed
-    if source_sample_rate != 16000 {
-        use rubato::{SincFixedIn, SincInterpolationType, SincInterpolationParameters, WindowFunction};
-
-        let params = SincInterpolationParameters {
-            sinc_len: 128,
-            f_cutoff: 0.95,
-            interpolation: SincInterpolationType::Linear,
-            window: WindowFunction::BlackmanHarris2,
-            oversampling_factor: 128,
-        };
-        
-        let mut resampler = SincFixedIn::<f32>::new(
-            16000.0 / source_sample_rate as f64,
-            2.0,
-            params,
-            samples.len(),
-            1
-        ).map_err(|_| anyhow!("resampler init failed"))?;
-        
-        let waves_in = vec![samples];
-        let waves_out = resampler.process(&waves_in, None).map_err(|_| anyhow!("resampling failed"))?;
-        
-        return Ok(waves_out[0].clone());
+    if source_sample_rate == WHISPER_SAMPLE_RATE {
+        return Ok(samples);
     }
+    resample_to_16k(&samples, source_sample_rate)
+}
 
-    Ok(samples)
+/// Resample mono audio to 16kHz with a high-quality sinc filter. The
+/// output is aligned with the input and `len * 16000 / rate` samples long.
+fn resample_to_16k(samples: &[f32], source_sample_rate: u32) -> Result<Vec<f32>> {
+    if source_sample_rate == WHISPER_SAMPLE_RATE {
+        return Ok(samples.to_vec());
+    }
+    use rubato::{SincFixedIn, SincInterpolationType, SincInterpolationParameters, WindowFunction};
+
+    let params = SincInterpolationParameters {
+        sinc_len: 128,
+        f_cutoff: 0.95,
+        interpolation: SincInterpolationType::Linear,
+        window: WindowFunction::BlackmanHarris2,
+        oversampling_factor: 128,
+    };
+    
+    let ratio = WHISPER_SAMPLE_RATE as f64 / source_sample_rate as f64;
+    let mut resampler = SincFixedIn::<f32>::new(
+        ratio,
+        2.0,
+        params,
+        FILE_RESAMPLER_CHUNK,
+        1
+    ).map_err(|_| anyhow!("resampler init failed"))?;
+    
+    // Feed fixed-size blocks so memory use doesn't grow with the file length.
+    // The sinc resampler's output is already aligned with its input.
+    let expected = (samples.len() as f64 * ratio).round() as usize;
+    let mut resampled = Vec::with_capacity(expected);
+    let mut blocks = samples.chunks_exact(FILE_RESAMPLER_CHUNK);
+    for block in &mut blocks {
+        let out = resampler.process(&[block], None).map_err(|_| anyhow!("resampling failed"))?;
+        resampled.extend_from_slice(&out[0]);
+    }
+    let rest = blocks.remainder();
+    if !rest.is_empty() {
+        let out = resampler.process_partial(Some(&[rest]), None).map_err(|_| anyhow!("resampling failed"))?;
+        resampled.extend_from_slice(&out[0]);
+    }
+    // Flush what the filter still holds
+    while resampled.len() < expected {
+        let out = resampler.process_partial::<&[f32]>(None, None).map_err(|_| anyhow!("resampling failed"))?;
+        if out[0].is_empty() {
+            break;
+        }
+        resampled.extend_from_slice(&out[0]);
+    }
+    resampled.truncate(expected);
+    
+    Ok(resampled)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_samples_convert_to_full_scale() {
+        assert_eq!(i16::MIN.to_f32_sample(), -1.0);
+        assert!((i16::MAX.to_f32_sample() - 1.0).abs() < 1e-4);
+        assert_eq!(0i16.to_f32_sample(), 0.0);
+        assert_eq!(32768u16.to_f32_sample(), 0.0);
+        assert_eq!(u16::MIN.to_f32_sample(), -1.0);
+        assert!((u16::MAX.to_f32_sample() - 1.0).abs() < 1e-4);
+        assert_eq!(i32::MIN.to_f32_sample(), -1.0);
+        assert!((i32::MAX.to_f32_sample() - 1.0).abs() < 1e-6);
+        assert_eq!(convert_samples(&[0.5f32, -0.25]), [0.5, -0.25]);
+    }
+
+    #[test]
+    fn agc_raises_quiet_input_toward_target() {
+        let mut gain = GainControl::new(0.0, true);
+        let quiet = || [0.007f32, -0.007].repeat(800);
+        let mut previous = rms(&quiet());
+        for _ in 0..100 {
+            let mut block = quiet();
+            gain.apply(&mut block);
+            let level = rms(&block);
+            assert!(level >= previous && level <= AGC_TARGET_RMS + 1e-4, "{level}");
+            previous = level;
+        }
+        assert!((previous - AGC_TARGET_RMS).abs() < 0.01, "{previous}");
+    }
+
+    #[test]
+    fn boosted_peaks_stay_within_full_scale() {
+        let mut gain = GainControl::new(24.0, false);
+        let mut block = [0.8f32, -0.8, 0.1, -0.3];
+        gain.apply(&mut block);
+        assert!(block.iter().all(|x| (-1.0..=1.0).contains(x)));
+        assert!(block[0] > 0.8);
+    }
+
+    #[test]
+    fn unity_gain_leaves_input_untouched() {
+        let mut gain = GainControl::new(0.0, false);
+        let mut block = [1.0f32, -1.0, 0.5];
+        gain.apply(&mut block);
+        assert_eq!(block, [1.0, -1.0, 0.5]);
+    }
+
+    #[test]
+    fn planes_mix_to_the_per_frame_average() {
+        let left = [1.0f32, 0.5, -1.0, 0.25];
+        let right = [0.0f32, 0.5, 1.0];
+        // The shorter plane sets the length
+        assert_eq!(downmix_planes(&[&left, &right], ChannelSelect::Mix), [0.5, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn channel_select_picks_or_mixes_interleaved_frames() {
+        let stereo = [0.2f32, 0.4, -0.6, 0.0, 1.0, 1.0];
+        assert_eq!(ChannelSelect::Mix.downmix(&stereo, 2), [0.3, -0.3, 1.0]);
+        assert_eq!(ChannelSelect::Channel(0).downmix(&stereo, 2), [0.2, -0.6, 1.0]);
+        assert_eq!(ChannelSelect::Channel(1).downmix(&stereo, 2), [0.4, 0.0, 1.0]);
+        // A channel the input doesn't have falls back to the mix
+        assert_eq!(ChannelSelect::Channel(2).downmix(&stereo, 2), [0.3, -0.3, 1.0]);
+        assert_eq!(ChannelSelect::from_settings(2), ChannelSelect::Channel(1));
+    }
+
+    #[test]
+    fn channel_select_applies_to_planes() {
+        let left = [0.2f32, -0.6];
+        let right = [0.4f32, 0.0];
+        assert_eq!(downmix_planes(&[&left, &right], ChannelSelect::Channel(1)), right);
+        assert_eq!(downmix_planes(&[&left, &right], ChannelSelect::Channel(5)), [0.3, -0.3]);
+    }
+
+    /// `frames` 30ms frames of a square wave at `level`
+    fn tone(level: f32, frames: usize) -> Vec<f32> {
+        (0..frames * VAD_FRAME).map(|i| if i % 2 == 0 { level } else { -level }).collect()
+    }
+
+    #[test]
+    fn near_silence_is_not_speech() {
+        assert!(!contains_speech(&tone(0.001, 50), SPEECH_RMS_THRESHOLD));
+        assert!(!contains_speech(&tone(0.0, 50), SPEECH_RMS_THRESHOLD));
+        assert!(contains_speech(&tone(0.2, 50), SPEECH_RMS_THRESHOLD));
+        // One loud frame in 50 is below the 10% needed
+        let mut mostly_quiet = tone(0.2, 1);
+        mostly_quiet.extend(tone(0.001, 49));
+        assert!(!contains_speech(&mostly_quiet, SPEECH_RMS_THRESHOLD));
+        // Blocks shorter than a frame are judged as a whole
+        assert!(contains_speech(&[0.5, -0.5, 0.5], SPEECH_RMS_THRESHOLD));
+    }
+
+    #[test]
+    fn only_the_tail_counts_as_trailing_silence() {
+        let mut block = tone(0.001, 3);
+        block.extend(tone(0.2, 2));
+        block.extend(tone(0.001, 4));
+        assert_eq!(trailing_silence(&block, SPEECH_RMS_THRESHOLD), 4 * VAD_FRAME);
+        block.extend(tone(0.2, 1));
+        assert_eq!(trailing_silence(&block, SPEECH_RMS_THRESHOLD), 0);
+    }
+
+    #[test]
+    fn zero_threshold_only_skips_digital_silence() {
+        assert!(contains_speech(&tone(0.0001, 10), 0.0));
+        assert!(!contains_speech(&tone(0.0, 10), 0.0));
+        let mut block = tone(0.0001, 2);
+        block.extend(tone(0.0, 3));
+        assert_eq!(trailing_silence(&block, 0.0), 3 * VAD_FRAME);
+    }
+
+    #[test]
+    fn resampling_keeps_length_and_timing() {
+        for rate in [44_100u32, 48_000, 8_000] {
+            // Not a whole number of resampler blocks
+            let len = FILE_RESAMPLER_CHUNK * 2 + 1234;
+            let sine = |t: f64| (2.0 * std::f64::consts::PI * 440.0 * t).sin() as f32 * 0.5;
+            let input: Vec<f32> = (0..len).map(|i| sine(i as f64 / rate as f64)).collect();
+            let output = resample_to_16k(&input, rate).unwrap();
+            let expected_len = (len as f64 * 16_000.0 / rate as f64).round() as usize;
+            assert_eq!(output.len(), expected_len, "{rate} Hz");
+            // Away from the edges the output is the same tone, in phase to
+            // within a sample (the tone moves up to 0.086 per sample)
+            for (i, &x) in output.iter().enumerate().skip(200).take(expected_len - 400) {
+                assert!((x - sine(i as f64 / 16_000.0)).abs() < 0.1, "{rate} Hz, sample {i}: {x}");
+            }
+        }
+        // A click lands where it was, not shifted by the filter delay
+        let mut click = vec![0.0f32; 30_000];
+        click[12_000] = 1.0;
+        let output = resample_to_16k(&click, 48_000).unwrap();
+        let peak = output.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+        assert!(peak.abs_diff(4_000) <= 1, "{peak}");
+        assert_eq!(resample_to_16k(&[0.5, -0.5], 16_000).unwrap(), [0.5, -0.5]);
+    }
+
+    #[test]
+    fn recording_round_trips_through_wav() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions").join("take.wav");
+        let chunks = [vec![0.0f32, 0.25, -0.5], vec![1.0, -1.0], Vec::new(), vec![0.125]];
+        let mut writer = create_recorder(&path).unwrap();
+        for chunk in &chunks {
+            write_recording(&mut writer, chunk).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        let spec = reader.spec();
+        assert_eq!((spec.sample_rate, spec.channels), (WHISPER_SAMPLE_RATE, 1));
+        let samples: Vec<f32> = reader.samples::<f32>().map(|s| s.unwrap()).collect();
+        assert_eq!(samples, chunks.concat());
+    }
+}
@@ -0,0 +1,218 @@
+//! Headless command-line transcription
+//!
+//! `rustvoice --transcribe input.wav --model base.en --output out.srt`
+//! transcribes one file and exits without opening the window, so the app
+//! can be scripted in pipelines and CI.
+
+use crate::audio::{self, ChannelSelect};
+use crate::decoder::{Task, Thresholds};
+use crate::export;
+use crate::model::{ModelManager, WhisperModel};
+use crate::settings::AppSettings;
+use crate::transcribe::{build_prompt, segments_text, TranscribeOptions, WhisperTranscriber};
+use anyhow::{anyhow, bail, Result};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Exit code for a failed download, decode or transcription
+const EXIT_FAILURE: i32 = 1;
+/// Exit code for bad command-line arguments
+const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "\
+Usage: rustvoice --transcribe <FILE> [OPTIONS]
+
+Options:
+  --model <NAME>        tiny.en, base.en, small.en, tiny, base, small, medium,
+                        large, large_v3, tiny_en_q80 or tiny_q80
+                        (default: the model selected in the app)
+  --output <FILE>       Write the transcript here instead of stdout;
+                        .srt and .vtt write subtitles
+  --task <TASK>         transcribe (default) or translate (to English)
+  --language <CODE>     Spoken language for multilingual models, e.g. de
+                        (default: auto-detect)
+  --timestamps          Prefix each segment with its [mm:ss] start time
+  --precision <P>       auto, f16 or f32 (default: the app setting)
+  --device <D>          auto, cpu, cuda or metal (default: the app setting)
+  --model-dir <DIR>     Load the model files from this folder instead of
+                        downloading (default: the app setting)
+  -h, --help            Show this help";
+
+/// What the command line asked for
+struct CliArgs {
+    input: PathBuf,
+    model: WhisperModel,
+    output: Option<PathBuf>,
+    task: Task,
+    language: Option<String>,
+    timestamps: bool,
+    precision: String,
+    device: String,
+    model_dir: Option<PathBuf>,
+}
+
+/// Whether the arguments ask for headless mode instead of the window
+pub fn wants_cli(args: &[String]) -> bool {
+    args.iter().any(|a| a == "--transcribe" || a == "--help" || a == "-h")
+}
+
+/// Run headless and return the process exit code
+pub fn run(args: &[String]) -> i32 {
+    let settings = AppSettings::load();
+    let cli = match parse_args(args, &settings) {
+        Ok(Some(cli)) => cli,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return 0;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    match transcribe(&cli, &settings) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Parse the arguments, or `None` when help was asked for
+fn parse_args(args: &[String], settings: &AppSettings) -> Result<Option<CliArgs>> {
+    let mut input = None;
+    let mut model = WhisperModel::from_settings_str(&settings.model_size);
+    let mut output = None;
+    let mut task = Task::Transcribe;
+    let mut language = None;
+    let mut timestamps = false;
+    let mut precision = settings.precision.clone();
+    let mut device = settings.device.clone();
+    let mut model_dir = Some(settings.model_dir.trim()).filter(|d| !d.is_empty()).map(PathBuf::from);
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("{} needs a value", arg));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--transcribe" => input = Some(PathBuf::from(value()?)),
+            "--model" => {
+                let name = value()?;
+                model = WhisperModel::parse(name).ok_or_else(|| anyhow!("unknown model '{}'", name))?;
+            }
+            "--output" => output = Some(PathBuf::from(value()?)),
+            "--task" => {
+                task = match value()?.as_str() {
+                    "transcribe" => Task::Transcribe,
+                    "translate" => Task::Translate,
+                    other => bail!("unknown task '{}'", other),
+                }
+            }
+            "--language" => language = Some(value()?.to_lowercase()),
+            "--timestamps" => timestamps = true,
+            "--precision" => {
+                precision = value()?.to_lowercase();
+                if !matches!(precision.as_str(), "auto" | "f16" | "f32") {
+                    bail!("unknown precision '{}'", precision);
+                }
+            }
+            "--device" => {
+                device = value()?.to_lowercase();
+                if !matches!(device.as_str(), "auto" | "cpu" | "cuda" | "metal") {
+                    bail!("unknown device '{}'", device);
+                }
+            }
+            "--model-dir" => model_dir = Some(PathBuf::from(value()?)),
+            other => bail!("unexpected argument '{}'", other),
+        }
+    }
+
+    let input = input.ok_or_else(|| anyhow!("--transcribe <FILE> is required"))?;
+    Ok(Some(CliArgs { input, model, output, task, language, timestamps, precision, device, model_dir }))
+}
+
+/// Fetch the model, transcribe the file and write the result
+fn transcribe(cli: &CliArgs, settings: &AppSettings) -> Result<()> {
+    let hf_token = Some(settings.hf_token.trim().to_string()).filter(|t| !t.is_empty());
+    let mut manager = ModelManager::new(hf_token)?;
+    manager.set_local_dir(cli.model_dir.clone());
+    // Report each file's download once per percent, on stderr so stdout stays clean
+    let last_reported = Mutex::new((String::new(), u64::MAX));
+    manager.set_progress(move |file, downloaded, total| {
+        let Some(total) = total.filter(|t| *t > 0) else { return };
+        let percent = downloaded * 100 / total;
+        let mut last = last_reported.lock().unwrap();
+        if last.0 != file || last.1 != percent {
+            *last = (file.to_string(), percent);
+            eprint!("\rDownloading {}: {}%", file, percent);
+            if downloaded >= total {
+                eprintln!();
+            }
+        }
+    });
+
+    let fetch = if cli.model_dir.is_some() { "Local model load" } else { "Download" };
+    let model_paths = manager.fetch_model(cli.model).map_err(|e| anyhow!("{} failed: {}", fetch, e))?;
+    let mel_paths = manager
+        .fetch_mel_filters(cli.model.num_mel_bins())
+        .map_err(|e| anyhow!("Mel filter {} failed: {}", fetch.to_lowercase(), e))?;
+    let transcriber = WhisperTranscriber::new(model_paths, mel_paths, &cli.precision, &cli.device)
+        .map_err(|e| anyhow!("Load failed: {}", e))?;
+    eprintln!("Using {}", transcriber.get_device_name());
+
+    if !transcriber.is_multilingual() {
+        if cli.task == Task::Translate {
+            bail!("{} is English-only and can't translate; pick a multilingual model", cli.model.display_name());
+        }
+        if cli.language.is_some() {
+            eprintln!("warning: {} is English-only; ignoring --language", cli.model.display_name());
+        }
+    } else if let Some(code) = &cli.language {
+        if transcriber.language_token(code).is_none() {
+            bail!("{} doesn't know language '{}'", cli.model.display_name(), code);
+        }
+    }
+
+    let samples = audio::decode_audio_file(&cli.input, ChannelSelect::from_settings(settings.input_channel))
+        .map_err(|e| anyhow!("Cannot read {}: {}", cli.input.display(), e))?;
+    let options = TranscribeOptions {
+        greedy_only: settings.greedy_only,
+        beam_size: settings.beam_size,
+        max_repeats: settings.max_repeats,
+        thresholds: Thresholds {
+            no_speech: settings.no_speech_threshold,
+            logprob: settings.logprob_threshold,
+            compression_ratio: settings.compression_ratio_threshold,
+        },
+        normalize: settings.normalize_input.clone(),
+        start_temperature: settings.start_temperature,
+        prompt: build_prompt(&settings.initial_prompt, settings.punctuation_priming),
+        timestamps: cli.timestamps,
+        word_timestamps: settings.word_timestamps,
+        task: cli.task,
+        language: cli.language.clone().filter(|_| transcriber.is_multilingual()),
+        ..Default::default()
+    };
+    let segments = transcriber.transcribe_segments(&samples, &options)?;
+
+    let extension = cli
+        .output
+        .as_ref()
+        .and_then(|p| p.extension())
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    let content = match extension.as_deref() {
+        Some("srt") => export::srt(&segments),
+        Some("vtt") => export::vtt(&segments),
+        _ => format!("{}\n", segments_text(&segments, 0.0, cli.timestamps)),
+    };
+    match &cli.output {
+        Some(path) => {
+            std::fs::write(path, content).map_err(|e| anyhow!("Cannot write {}: {}", path.display(), e))?;
+            eprintln!("Wrote {}", path.display());
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
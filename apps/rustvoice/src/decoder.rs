@@ -1,60 +1,95 @@
 use anyhow::{Error as E, Result, anyhow, bail};
 use candle_core as candle;
-use candle_core::{Device, IndexOp, Tensor};
+use candle_core::{DType, Device, IndexOp, Tensor};
 use candle_nn::{ops::{log_softmax, softmax}, VarBuilder};
 use rand::{rngs::StdRng, SeedableRng, Rng};
 use rand::distributions::{WeightedIndex, Distribution};
 use tokenizers::Tokenizer;
-use candle_transformers::models::whisper::{self as m, Config};
+use candle_transformers::models::whisper as m;
+use crate::timing;
+use crate::whisper_model::Whisper;
 
 pub const NO_SPEECH_THRESHOLD: f64 = 0.6;
 pub const LOGPROB_THRESHOLD: f64 = -1.0;
 pub const COMPRESSION_RATIO_THRESHOLD: f64 = 2.4;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum Task {
-    Transcribe,
-    Translate,
+/// When a decode is retried hotter or dropped as silence
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Thresholds {
+    pub no_speech: f64,         // No-speech probability above which a quiet segment is skipped
+    pub logprob: f64,           // Average log probability below which a decode is retried
+    pub compression_ratio: f64, // Compression ratio above which a decode is retried
 }
 
-pub enum Model {
-    Normal(m::model::Whisper),
-    Quantized(m::quantized_model::Whisper),
-}
-
-impl Model {
-    pub fn config(&self) -> &Config {
-        match self {
-            Self::Normal(m) => &m.config,
-            Self::Quantized(m) => &m.config,
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            no_speech: NO_SPEECH_THRESHOLD,
+            logprob: LOGPROB_THRESHOLD,
+            compression_ratio: COMPRESSION_RATIO_THRESHOLD,
         }
     }
+}
 
-    pub fn encoder_forward(&mut self, x: &Tensor, flush: bool) -> candle::Result<Tensor> {
-        match self {
-            Self::Normal(m) => m.encoder.forward(x, flush),
-            Self::Quantized(m) => m.encoder.forward(x, flush),
-        }
+/// Ratio of the text's byte length to its zlib-compressed length
+///
+/// Same measure as OpenAI's reference implementation: looping, repetitive
+/// output compresses well and scores above `COMPRESSION_RATIO_THRESHOLD`.
+pub fn compression_ratio(text: &str) -> f64 {
+    use std::io::Write;
+    if text.is_empty() {
+        return 0.0;
     }
-
-    pub fn decoder_forward(
-        &mut self,
-        x: &Tensor,
-        xa: &Tensor,
-        flush: bool,
-    ) -> candle::Result<Tensor> {
-        match self {
-            Self::Normal(m) => m.decoder.forward(x, xa, flush),
-            Self::Quantized(m) => m.decoder.forward(x, xa, flush),
-        }
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    let compressed = encoder
+        .write_all(text.as_bytes())
+        .and_then(|_| encoder.finish());
+    match compressed {
+        Ok(bytes) if !bytes.is_empty() => text.len() as f64 / bytes.len() as f64,
+        _ => 0.0,
     }
+}
+
+/// Whether a decode should be retried at the next temperature: its text is
+/// too repetitive (compression ratio or a `repeats` loop) or the model was
+/// unsure of it. Likely silence is kept as is, since a hotter pass would
+/// only invent words.
+pub fn needs_fallback(dr: &DecodingResult, thresholds: &Thresholds, repeats: bool) -> bool {
+    let poor = dr.compression_ratio > thresholds.compression_ratio || dr.avg_logprob < thresholds.logprob || repeats;
+    poor && dr.no_speech_prob <= thresholds.no_speech
+}
+
+/// Longest phrase, in tokens, checked for repetition loops
+const MAX_REPEAT_NGRAM: usize = 16;
 
-    pub fn decoder_final_linear(&self, x: &Tensor) -> candle::Result<Tensor> {
-        match self {
-            Self::Normal(m) => m.decoder.final_linear(x),
-            Self::Quantized(m) => m.decoder.final_linear(x),
+/// Find a repetition loop: an n-gram of up to `MAX_REPEAT_NGRAM` tokens
+/// repeated back to back more than `max_repeats` times, as in "you you you
+/// you you". Returns the start and length of the earliest such n-gram, so
+/// cutting at `start + len` keeps one copy. `max_repeats` 0 disables the check.
+pub fn find_repetition(tokens: &[u32], max_repeats: usize) -> Option<(usize, usize)> {
+    if max_repeats == 0 {
+        return None;
+    }
+    for start in 0..tokens.len() {
+        for len in 1..=MAX_REPEAT_NGRAM.min((tokens.len() - start) / (max_repeats + 1)) {
+            let ngram = &tokens[start..start + len];
+            let repeats = tokens[start..]
+                .chunks_exact(len)
+                .take_while(|chunk| *chunk == ngram)
+                .count();
+            if repeats > max_repeats {
+                return Some((start, len));
+            }
         }
     }
+    None
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Task {
+    #[default]
+    Transcribe,
+    Translate,
 }
 
 #[derive(Debug, Clone)]
@@ -72,30 +107,36 @@ pub struct Segment {
     pub start: f64,
     pub duration: f64,
     pub dr: DecodingResult,
+    pub words: Vec<(String, f64, f64)>, // (word, start, end), filled when word timestamps are on
 }
 
 pub struct Decoder {
-    model: Model,
+    model: Whisper,
     rng: StdRng,
+    seed: u64,
     task: Option<Task>,
     timestamps: bool,
     max_initial_timestamp_index: Option<u32>,
     verbose: bool,
     tokenizer: Tokenizer,
     suppress_tokens: Tensor,
-    sot_token: u32,
-    transcribe_token: u32,
-    translate_token: u32,
-    eot_token: u32,
-    no_speech_token: u32,
-    no_timestamps_token: u32,
+    suppress_tokens_ts: Tensor, // Also suppresses <|notimestamps|>, used with timestamps
+    special: SpecialTokens,
     language_token: Option<u32>,
+    greedy_only: bool,
+    start_temperature: f64,
+    beam_size: usize, // Hypotheses kept by temperature-0 decoding; 1 = greedy
+    max_repeats: usize, // Back-to-back repeats of a phrase counted as a loop; 0 = no check
+    thresholds: Thresholds,
+    word_timestamps: bool,
+    audio_features: Option<Tensor>, // Encoder output of the last decode, for word alignment
+    prompt_tokens: Vec<u32>,
 }
 
 impl Decoder {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        model: Model,
+        model: Whisper,
         tokenizer: Tokenizer,
         seed: u64,
         device: &Device,
@@ -105,96 +146,212 @@ impl Decoder {
         max_initial_timestamp_index: Option<u32>,
         verbose: bool,
     ) -> Result<Self> {
-        let no_timestamps_token = token_id(&tokenizer, m::NO_TIMESTAMPS_TOKEN)?;
-        let suppress_tokens: Vec<f32> = (0..model.config().vocab_size as u32)
-            .map(|i| {
-                if model.config().suppress_tokens.contains(&i)
-                    || timestamps && i == no_timestamps_token
-                {
-                    f32::NEG_INFINITY
-                } else {
-                    0f32
-                }
-            })
-            .collect();
-        let suppress_tokens = Tensor::new(suppress_tokens.as_slice(), device)?;
-        let sot_token = token_id(&tokenizer, m::SOT_TOKEN)?;
-        let transcribe_token = token_id(&tokenizer, m::TRANSCRIBE_TOKEN)?;
-        let translate_token = token_id(&tokenizer, m::TRANSLATE_TOKEN)?;
-        let eot_token = token_id(&tokenizer, m::EOT_TOKEN)?;
-        let no_speech_token = m::NO_SPEECH_TOKENS
-            .iter()
-            .find_map(|token| token_id(&tokenizer, token).ok());
-        let no_speech_token = match no_speech_token {
-            None => anyhow::bail!("unable to find any non-speech token"),
-            Some(n) => n,
+        let special = SpecialTokens::new(&tokenizer)?;
+        // Both masks are built once so `configure` can switch timestamps cheaply
+        let config = &model.config;
+        let mask = |timestamps| {
+            let mask = suppress_mask(config.vocab_size, &config.suppress_tokens, special.no_timestamps, timestamps);
+            Tensor::new(mask.as_slice(), device)
         };
+        let suppress_tokens = mask(false)?;
+        let suppress_tokens_ts = mask(true)?;
         Ok(Self {
             model,
             rng: StdRng::seed_from_u64(seed),
+            seed,
             tokenizer,
             task,
             timestamps,
             max_initial_timestamp_index,
             verbose,
             suppress_tokens,
-            sot_token,
-            transcribe_token,
-            translate_token,
-            eot_token,
-            no_speech_token,
+            suppress_tokens_ts,
+            special,
             language_token,
-            no_timestamps_token,
+            greedy_only: false,
+            beam_size: 1,
+            max_repeats: 0,
+            thresholds: Thresholds::default(),
+            word_timestamps: false,
+            audio_features: None,
+            start_temperature: 0.0,
+            prompt_tokens: Vec::new(),
+        })
+    }
+
+    /// Set the per-run language, task and timestamp mode of a reused decoder.
+    /// English-only models transcribe when asked to translate.
+    pub fn configure(&mut self, language_token: Option<u32>, task: Option<Task>, timestamps: bool) {
+        self.language_token = language_token;
+        self.task = match task {
+            Some(Task::Translate) if !is_multilingual(&self.model.config) => {
+                log::warn!("Translate needs a multilingual model, transcribing instead");
+                Some(Task::Transcribe)
+            }
+            task => task,
+        };
+        self.timestamps = timestamps;
+    }
+
+    /// Prompt and start-of-transcript tokens for the current configuration
+    fn start_tokens(&self) -> (Vec<u32>, usize) {
+        initial_tokens(&self.special, &self.prompt_tokens, self.language_token, self.task, self.timestamps)
+    }
+
+    /// Clear per-utterance state so a reused decoder behaves like a new one:
+    /// reseeds sampling, drops the prompt and decoding options and flushes
+    /// the KV cache
+    pub fn reset(&mut self) {
+        self.rng = StdRng::seed_from_u64(self.seed);
+        self.greedy_only = false;
+        self.start_temperature = 0.0;
+        self.beam_size = 1;
+        self.max_repeats = 0;
+        self.thresholds = Thresholds::default();
+        self.word_timestamps = false;
+        self.audio_features = None;
+        self.prompt_tokens.clear();
+        self.model.reset_kv_cache();
+    }
+
+    /// Decode each segment once at temperature 0, skipping the fallback ladder
+    pub fn set_greedy_only(&mut self, greedy_only: bool) {
+        self.greedy_only = greedy_only;
+    }
+
+    /// Use beam search with `beam_size` hypotheses for temperature-0 decodes
+    /// (1 = greedy). Every beam runs its own decoder pass at each step.
+    pub fn set_beam_size(&mut self, beam_size: usize) {
+        self.beam_size = beam_size.max(1);
+    }
+
+    /// Treat a phrase repeated back to back more than `max_repeats` times as a
+    /// hallucination loop: retry hotter, then cut the output after its first
+    /// copy (0 = off)
+    pub fn set_max_repeats(&mut self, max_repeats: usize) {
+        self.max_repeats = max_repeats;
+    }
+
+    /// Set when the fallback ladder retries a decode and when a segment is
+    /// skipped as silence
+    pub fn set_thresholds(&mut self, thresholds: Thresholds) {
+        self.thresholds = thresholds;
+    }
+
+    /// Attach per-word times to each segment, aligned through the decoder's
+    /// cross-attention. Costs one more decoder pass per segment.
+    pub fn set_word_timestamps(&mut self, word_timestamps: bool) {
+        self.word_timestamps = word_timestamps;
+    }
+
+    /// Condition decoding on previous text (`<|startofprev|>` prompt). Only the
+    /// last tokens that fit in half the context window are kept.
+    pub fn set_prompt(&mut self, prompt_tokens: Vec<u32>) {
+        let max_len = self.model.config.max_target_positions / 2 - 1;
+        let skip = prompt_tokens.len().saturating_sub(max_len);
+        self.prompt_tokens = prompt_tokens[skip..].to_vec();
+    }
+
+    /// Number of tokens the prompt adds in front of the start-of-transcript token
+    fn prompt_prefix_len(&self) -> usize {
+        match self.special.sot_prev {
+            Some(_) if !self.prompt_tokens.is_empty() => self.prompt_tokens.len() + 1,
+            _ => 0,
+        }
+    }
+
+    /// Skip fallback temperatures below `t` (the first decode runs at the lowest remaining one)
+    pub fn set_start_temperature(&mut self, t: f64) {
+        self.start_temperature = t;
+    }
+
+    /// Logits for the token after `tokens`, with timestamp rules and token
+    /// suppression applied. `first` flushes the cross-attention cache and also
+    /// returns the no-speech probability.
+    fn next_logits(
+        &mut self,
+        tokens: &[u32],
+        audio_features: &Tensor,
+        prompt_len: usize,
+        first: bool,
+    ) -> Result<(Tensor, Option<f64>)> {
+        let tokens_t = Tensor::new(tokens, audio_features.device())?;
+        let tokens_t = tokens_t.unsqueeze(0)?;
+        let ys = self
+            .model
+            .decoder
+            .forward(&tokens_t, audio_features, first)?;
+
+        let mut no_speech_prob = None;
+        if first {
+            // No-speech probability is read at the start-of-transcript position
+            let logits = self
+                .model
+                .decoder
+                .final_linear(&ys.i((..1, prompt_len..prompt_len + 1))?)?
+                .i(0)?
+                .i(0)?
+                .to_dtype(DType::F32)?;
+            no_speech_prob = Some(softmax(&logits, 0)?
+                .i(self.special.no_speech as usize)?
+                .to_scalar::<f32>()? as f64);
+        }
+
+        let (_, seq_len, _) = ys.dims3()?;
+        let logits = self
+            .model
+            .decoder
+            .final_linear(&ys.i((..1, seq_len - 1..))?)?
+            .i(0)?
+            .i(0)?
+            .to_dtype(DType::F32)?; // Half-precision models still sample in f32
+
+        let logits = if self.timestamps {
+            self.apply_timestamp_rules(&logits, tokens)?
+        } else {
+            logits
+        };
+
+        let suppress_tokens = if self.timestamps { &self.suppress_tokens_ts } else { &self.suppress_tokens };
+        Ok((logits.broadcast_add(suppress_tokens)?, no_speech_prob))
+    }
+
+    /// Build the result of a finished hypothesis, leaving the prompt out
+    fn finish(&self, mut tokens: Vec<u32>, prompt_len: usize, sum_logprob: f64, no_speech_prob: f64, t: f64) -> Result<DecodingResult> {
+        let tokens = tokens.split_off(prompt_len);
+        let text = self.tokenizer.decode(&tokens, true).map_err(E::msg)?;
+        let avg_logprob = sum_logprob / tokens.len() as f64;
+        let compression_ratio = compression_ratio(&text);
+
+        Ok(DecodingResult {
+            tokens,
+            text,
+            avg_logprob,
+            no_speech_prob,
+            temperature: t,
+            compression_ratio,
         })
     }
 
     pub fn decode(&mut self, mel: &Tensor, t: f64) -> Result<DecodingResult> {
-        let audio_features = self.model.encoder_forward(mel, true)?;
+        let audio_features = self.model.encoder.forward(mel, true)?;
         if self.verbose {
             println!("audio features: {:?}", audio_features.dims());
         }
-        let sample_len = self.model.config().max_target_positions / 2;
+        self.audio_features = Some(audio_features.clone());
+        if t == 0.0 && self.beam_size > 1 {
+            return self.decode_beam(&audio_features);
+        }
+        let sample_len = self.model.config.max_target_positions / 2;
         let mut sum_logprob = 0f64;
         let mut no_speech_prob = f64::NAN;
-        let mut tokens = vec![self.sot_token];
-        if let Some(language_token) = self.language_token {
-            tokens.push(language_token);
-        }
-        match self.task {
-            None | Some(Task::Transcribe) => tokens.push(self.transcribe_token),
-            Some(Task::Translate) => tokens.push(self.translate_token),
-        }
-        if !self.timestamps {
-            tokens.push(self.no_timestamps_token);
-        }
+        let (mut tokens, prompt_len) = self.start_tokens();
         for i in 0..sample_len {
-            let tokens_t = Tensor::new(tokens.as_slice(), mel.device())?;
-            let tokens_t = tokens_t.unsqueeze(0)?;
-            let ys = self
-                .model
-                .decoder_forward(&tokens_t, &audio_features, i == 0)?;
-
-            if i == 0 {
-                let logits = self.model.decoder_final_linear(&ys.i(..1)?)?.i(0)?.i(0)?;
-                no_speech_prob = softmax(&logits, 0)?
-                    .i(self.no_speech_token as usize)?
-                    .to_scalar::<f32>()? as f64;
+            let (logits, first_no_speech) = self.next_logits(&tokens, &audio_features, prompt_len, i == 0)?;
+            if let Some(p) = first_no_speech {
+                no_speech_prob = p;
             }
 
-            let (_, seq_len, _) = ys.dims3()?;
-            let logits = self
-                .model
-                .decoder_final_linear(&ys.i((..1, seq_len - 1..))?)?
-                .i(0)?
-                .i(0)?;
-
-            let logits = if self.timestamps {
-                self.apply_timestamp_rules(&logits, &tokens)?
-            } else {
-                logits
-            };
-
-            let logits = logits.broadcast_add(&self.suppress_tokens)?;
             let next_token = if t > 0f64 {
                 let prs = softmax(&(&logits / t)?, 0)?;
                 let logits_v: Vec<f32> = prs.to_vec1()?;
@@ -213,37 +370,95 @@ impl Decoder {
             let prob = softmax(&logits, candle::D::Minus1)?
                 .i(next_token as usize)?
                 .to_scalar::<f32>()? as f64;
-            if next_token == self.eot_token
-                || tokens.len() > self.model.config().max_target_positions
+            if next_token == self.special.eot
+                || tokens.len() > self.model.config.max_target_positions
             {
                 break;
             }
             sum_logprob += prob.ln();
         }
-        let text = self.tokenizer.decode(&tokens, true).map_err(E::msg)?;
-        let avg_logprob = sum_logprob / tokens.len() as f64;
+        self.finish(tokens, prompt_len, sum_logprob, no_speech_prob, t)
+    }
 
-        Ok(DecodingResult {
-            tokens,
-            text,
-            avg_logprob,
-            no_speech_prob,
-            temperature: t,
-            compression_ratio: f64::NAN, // Not calculated
-        })
+    /// Temperature-0 beam search: keep the `beam_size` most likely hypotheses
+    /// at each step and return the finished one with the best
+    /// length-normalized log probability.
+    fn decode_beam(&mut self, audio_features: &Tensor) -> Result<DecodingResult> {
+        let sample_len = self.model.config.max_target_positions / 2;
+        let max_len = self.model.config.max_target_positions;
+        let beam_size = self.beam_size;
+        let mut no_speech_prob = f64::NAN;
+        let (initial, prompt_len) = self.start_tokens();
+        let mut beams: Vec<(Vec<u32>, f64)> = vec![(initial, 0.0)];
+        let mut finished: Vec<(Vec<u32>, f64)> = Vec::new();
+
+        for i in 0..sample_len {
+            let mut candidates: Vec<(Vec<u32>, f64, bool)> = Vec::new();
+            for (tokens, sum_logprob) in &beams {
+                let (logits, first_no_speech) = self.next_logits(tokens, audio_features, prompt_len, i == 0)?;
+                if let Some(p) = first_no_speech {
+                    no_speech_prob = p;
+                }
+                let logprobs: Vec<f32> = log_softmax(&logits, 0)?.to_vec1()?;
+                let mut ranked: Vec<(usize, f32)> = logprobs
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, lp)| lp.is_finite())
+                    .collect();
+                ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+                for (token, lp) in ranked.into_iter().take(beam_size) {
+                    let mut next = tokens.clone();
+                    next.push(token as u32);
+                    let done = token as u32 == self.special.eot || next.len() > max_len;
+                    // Like the greedy path, the end token doesn't count towards the score
+                    let sum = if token as u32 == self.special.eot { *sum_logprob } else { sum_logprob + lp as f64 };
+                    candidates.push((next, sum, done));
+                }
+            }
+            candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+            beams.clear();
+            for (tokens, sum, done) in candidates {
+                if done {
+                    if finished.len() < beam_size {
+                        finished.push((tokens, sum));
+                    }
+                } else if beams.len() < beam_size {
+                    beams.push((tokens, sum));
+                }
+            }
+            if finished.len() >= beam_size || beams.is_empty() {
+                break;
+            }
+        }
+        // Out of steps: unfinished hypotheses compete too
+        if finished.is_empty() {
+            finished = beams;
+        }
+
+        let score = |(tokens, sum): &(Vec<u32>, f64)| sum / (tokens.len() - prompt_len) as f64;
+        let (tokens, sum_logprob) = finished
+            .into_iter()
+            .max_by(|a, b| score(a).total_cmp(&score(b)))
+            .ok_or_else(|| anyhow!("beam search produced no hypothesis"))?;
+        self.finish(tokens, prompt_len, sum_logprob, no_speech_prob, 0.0)
     }
 
     pub fn decode_with_fallback(&mut self, segment: &Tensor) -> Result<DecodingResult> {
-        for (i, &t) in m::TEMPERATURES.iter().enumerate() {
+        let first = m::TEMPERATURES
+            .iter()
+            .position(|&t| t >= self.start_temperature)
+            .unwrap_or(m::TEMPERATURES.len() - 1);
+        let temperatures = &m::TEMPERATURES[first..];
+        for (i, &t) in temperatures.iter().enumerate() {
             let dr: Result<DecodingResult> = self.decode(segment, t);
-            if i == m::TEMPERATURES.len() - 1 {
+            if i == temperatures.len() - 1 {
                 return dr;
             }
             match dr {
                 Ok(dr) => {
-                    let needs_fallback = dr.compression_ratio > COMPRESSION_RATIO_THRESHOLD
-                        || dr.avg_logprob < LOGPROB_THRESHOLD;
-                    if !needs_fallback || dr.no_speech_prob > NO_SPEECH_THRESHOLD {
+                    let repeats = self.repetition_cut(&dr.tokens).is_some();
+                    if !needs_fallback(&dr, &self.thresholds, repeats) {
                         return Ok(dr);
                     }
                 }
@@ -257,10 +472,10 @@ impl Decoder {
 
     fn apply_timestamp_rules(&self, input_logits: &Tensor, tokens: &[u32]) -> Result<Tensor> {
         let device = input_logits.device().clone();
-        let timestamp_begin = self.no_timestamps_token + 1;
-        let vocab_size = self.model.config().vocab_size as u32;
+        let timestamp_begin = self.special.no_timestamps + 1;
+        let vocab_size = self.model.config.vocab_size as u32;
 
-        let sample_begin = if self.language_token.is_some() { 3 } else { 2 };
+        let sample_begin = self.prompt_prefix_len() + if self.language_token.is_some() { 3 } else { 2 };
         let sampled_tokens = if tokens.len() > sample_begin {
             &tokens[sample_begin..]
         } else {
@@ -294,7 +509,7 @@ impl Decoder {
                     masks.push(Tensor::new(mask_buffer.as_slice(), &device)?);
                 } else {
                     for i in 0..vocab_size {
-                        mask_buffer[i as usize] = if i < self.eot_token {
+                        mask_buffer[i as usize] = if i < self.special.eot {
                             f32::NEG_INFINITY
                         } else {
                             0.0
@@ -399,28 +614,234 @@ impl Decoder {
         let mut seek = 0;
         let mut segments = vec![];
         while seek < content_frames {
-            // let start = std::time::Instant::now();
+            let start = std::time::Instant::now();
             let time_offset = (seek * m::HOP_LENGTH) as f64 / m::SAMPLE_RATE as f64;
             let segment_size = usize::min(content_frames - seek, m::N_FRAMES);
             let mel_segment = mel.narrow(2, seek, segment_size)?;
             let segment_duration = (segment_size * m::HOP_LENGTH) as f64 / m::SAMPLE_RATE as f64;
-            let dr = self.decode_with_fallback(&mel_segment)?;
+            let dr = if self.greedy_only {
+                self.decode(&mel_segment, 0.0)?
+            } else {
+                self.decode_with_fallback(&mel_segment)?
+            };
             seek += segment_size;
+            log::debug!("Decoded {:.1}s of audio in {:?} (beam size {})", segment_duration, start.elapsed(), self.beam_size);
             
-            if dr.no_speech_prob > NO_SPEECH_THRESHOLD && dr.avg_logprob < LOGPROB_THRESHOLD {
+            if dr.no_speech_prob > self.thresholds.no_speech && dr.avg_logprob < self.thresholds.logprob {
                 // println!("no speech detected, skipping {seek} {dr:?}");
                 continue;
             }
             
+            let dr = self.truncate_repetition(dr)?;
+            let words = if self.word_timestamps {
+                self.word_spans(&dr.tokens, time_offset, segment_size)?
+            } else {
+                Vec::new()
+            };
             let segment = Segment {
                 start: time_offset,
                 duration: segment_duration,
                 dr,
+                words,
             };
             segments.push(segment)
         }
         Ok(segments)
     }
+
+    /// Index in `tokens` where a repetition loop's second copy begins. Only
+    /// text tokens are compared, so timestamps between the copies don't hide it.
+    fn repetition_cut(&self, tokens: &[u32]) -> Option<usize> {
+        let text_positions: Vec<usize> = (0..tokens.len()).filter(|&i| tokens[i] < self.special.eot).collect();
+        let text_tokens: Vec<u32> = text_positions.iter().map(|&i| tokens[i]).collect();
+        let (start, len) = find_repetition(&text_tokens, self.max_repeats)?;
+        Some(text_positions[start + len])
+    }
+
+    /// Cut a repetition loop the fallback couldn't avoid down to one copy
+    fn truncate_repetition(&self, mut dr: DecodingResult) -> Result<DecodingResult> {
+        let Some(cut) = self.repetition_cut(&dr.tokens) else { return Ok(dr) };
+        dr.tokens.truncate(cut);
+        let text = self.tokenizer.decode(&dr.tokens, true).map_err(E::msg)?;
+        log::debug!("Repetition loop cut: {:?} -> {:?}", dr.text, text);
+        dr.compression_ratio = compression_ratio(&text);
+        dr.text = text;
+        Ok(dr)
+    }
+
+    /// Time each word of a segment by aligning its text tokens with the
+    /// audio through the cross-attention of the decoder's upper half of
+    /// layers, whose heads follow the audio most closely (the reference
+    /// implementation's default where a model has no tuned head list).
+    /// `mel_frames` is the segment's length; times are absolute.
+    fn word_spans(&mut self, tokens: &[u32], start: f64, mel_frames: usize) -> Result<Vec<(String, f64, f64)>> {
+        let text_tokens: Vec<u32> = tokens.iter().copied().filter(|&t| t < self.special.eot).collect();
+        let words = split_words(&self.tokenizer, &text_tokens, self.language_uses_spaces())?;
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+        let audio_features = self
+            .audio_features
+            .clone()
+            .ok_or_else(|| anyhow!("no audio features to align words with"))?;
+
+        // The decoding start without prompt or timestamps, then the text
+        let mut sequence = vec![self.special.sot];
+        sequence.extend(self.language_token);
+        sequence.push(match self.task {
+            Some(Task::Translate) => self.special.translate,
+            _ => self.special.transcribe,
+        });
+        let first_row = sequence.len(); // <|notimestamps|> is where the first text token is predicted
+        sequence.push(self.special.no_timestamps);
+        sequence.extend_from_slice(&text_tokens);
+        sequence.push(self.special.eot);
+
+        let tokens_t = Tensor::new(sequence.as_slice(), audio_features.device())?.unsqueeze(0)?;
+        let (_, cross_qk) = self
+            .model
+            .decoder
+            .forward_with_cross_attention(&tokens_t, &audio_features, true)?;
+        // Attention past the end of the audio only sees padding
+        let frames = (mel_frames / 2).clamp(1, audio_features.dim(1)?);
+        let mut scores = Vec::new();
+        let mut heads = 0;
+        for qk in &cross_qk[cross_qk.len() / 2..] {
+            let qk = qk.i(0)?.narrow(2, 0, frames)?;
+            heads += qk.dim(0)?;
+            scores.extend(qk.to_dtype(DType::F32)?.flatten_all()?.to_vec1::<f32>()?);
+        }
+        let matrix = timing::attention_matrix(&scores, heads, sequence.len(), frames);
+        let rows = &matrix[first_row * frames..(first_row + text_tokens.len() + 1) * frames];
+
+        let lengths: Vec<usize> = words.iter().map(|(_, len)| *len).collect();
+        let times = timing::word_times(rows, frames, &lengths);
+        Ok(words
+            .into_iter()
+            .zip(times)
+            .map(|((word, _), (word_start, word_end))| (word, start + word_start, start + word_end))
+            .collect())
+    }
+
+    /// Whether the pinned language separates words with spaces (Chinese,
+    /// Japanese, Thai, Lao, Burmese and Cantonese don't)
+    fn language_uses_spaces(&self) -> bool {
+        let language = self.language_token.and_then(|t| self.tokenizer.id_to_token(t));
+        !matches!(
+            language.as_deref(),
+            Some("<|zh|>" | "<|ja|>" | "<|th|>" | "<|lo|>" | "<|my|>" | "<|yue|>")
+        )
+    }
+}
+
+/// Group text tokens into words, as (word, token count)
+///
+/// Tokens are decoded one piece at a time, a piece spanning several tokens
+/// when a character does. With `on_spaces`, a piece starting with a space
+/// begins a new word and anything else (the rest of a word, punctuation)
+/// joins the previous one; otherwise every piece is a word.
+pub fn split_words(tokenizer: &Tokenizer, tokens: &[u32], on_spaces: bool) -> Result<Vec<(String, usize)>> {
+    let mut words: Vec<(String, usize)> = Vec::new();
+    let mut start = 0;
+    for end in 1..=tokens.len() {
+        let piece = tokenizer.decode(&tokens[start..end], true).map_err(E::msg)?;
+        if piece.contains('\u{FFFD}') && end < tokens.len() {
+            continue; // Incomplete character
+        }
+        let count = end - start;
+        start = end;
+        match words.last_mut() {
+            Some(last) if on_spaces && !piece.starts_with(' ') => {
+                last.0.push_str(&piece);
+                last.1 += count;
+            }
+            _ => words.push((piece, count)),
+        }
+    }
+    Ok(words
+        .into_iter()
+        .map(|(word, count)| (word.trim().to_string(), count))
+        .collect())
+}
+
+/// Ids of the special tokens decoding starts and stops with
+#[derive(Clone, Copy, Debug)]
+struct SpecialTokens {
+    sot: u32,
+    transcribe: u32,
+    translate: u32,
+    eot: u32,
+    no_speech: u32,
+    no_timestamps: u32, // Timestamp tokens follow it
+    sot_prev: Option<u32>,
+}
+
+impl SpecialTokens {
+    fn new(tokenizer: &Tokenizer) -> Result<Self> {
+        let no_speech = m::NO_SPEECH_TOKENS
+            .iter()
+            .find_map(|token| token_id(tokenizer, token).ok());
+        let no_speech = match no_speech {
+            None => anyhow::bail!("unable to find any non-speech token"),
+            Some(n) => n,
+        };
+        Ok(Self {
+            sot: token_id(tokenizer, m::SOT_TOKEN)?,
+            transcribe: token_id(tokenizer, m::TRANSCRIBE_TOKEN)?,
+            translate: token_id(tokenizer, m::TRANSLATE_TOKEN)?,
+            eot: token_id(tokenizer, m::EOT_TOKEN)?,
+            no_speech,
+            no_timestamps: token_id(tokenizer, m::NO_TIMESTAMPS_TOKEN)?,
+            sot_prev: token_id(tokenizer, "<|startofprev|>").ok(),
+        })
+    }
+}
+
+/// Prompt and start-of-transcript tokens every hypothesis begins with,
+/// and how many of them are prompt
+fn initial_tokens(
+    special: &SpecialTokens,
+    prompt_tokens: &[u32],
+    language_token: Option<u32>,
+    task: Option<Task>,
+    timestamps: bool,
+) -> (Vec<u32>, usize) {
+    let mut tokens = Vec::new();
+    if let (Some(sot_prev), true) = (special.sot_prev, !prompt_tokens.is_empty()) {
+        tokens.push(sot_prev);
+        tokens.extend_from_slice(prompt_tokens);
+    }
+    let prompt_len = tokens.len();
+    tokens.push(special.sot);
+    if let Some(language_token) = language_token {
+        tokens.push(language_token);
+    }
+    match task {
+        None | Some(Task::Transcribe) => tokens.push(special.transcribe),
+        Some(Task::Translate) => tokens.push(special.translate),
+    }
+    if !timestamps {
+        tokens.push(special.no_timestamps);
+    }
+    (tokens, prompt_len)
+}
+
+/// Logit mask, `-inf` for each token that may not be sampled: the model's
+/// suppressed tokens, plus `<|notimestamps|>` in timestamp mode or the
+/// timestamp tokens after it otherwise
+fn suppress_mask(vocab_size: usize, suppress_tokens: &[u32], no_timestamps_token: u32, timestamps: bool) -> Vec<f32> {
+    (0..vocab_size as u32)
+        .map(|i| {
+            let suppressed = suppress_tokens.contains(&i)
+                || if timestamps { i == no_timestamps_token } else { i > no_timestamps_token };
+            if suppressed { f32::NEG_INFINITY } else { 0f32 }
+        })
+        .collect()
+}
+
+/// English-only models (`*.en`) have a smaller vocabulary and can't translate
+pub fn is_multilingual(config: &m::Config) -> bool {
+    config.vocab_size >= 51865
 }
 
 pub fn token_id(tokenizer: &Tokenizer, token: &str) -> candle::Result<u32> {
@@ -429,3 +850,206 @@ pub fn token_id(tokenizer: &Tokenizer, token: &str) -> candle::Result<u32> {
         Some(id) => Ok(id),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whisper_model::Weights;
+
+    /// Whisper-like tokenizer: a few byte-level text tokens, then the end
+    /// token, the special tokens and two timestamps in Whisper's order
+    const SPECIAL: [&str; 10] = [
+        m::EOT_TOKEN, m::SOT_TOKEN, "<|en|>", m::TRANSLATE_TOKEN, m::TRANSCRIBE_TOKEN,
+        "<|startofprev|>", "<|nocaptions|>", m::NO_TIMESTAMPS_TOKEN, "<|0.00|>", "<|0.02|>",
+    ];
+
+    fn tokenizer() -> Tokenizer {
+        tokenizer_with(&SPECIAL)
+    }
+
+    /// Like an English-only model's tokenizer, without language tokens
+    fn english_tokenizer() -> Tokenizer {
+        let special: Vec<&str> = SPECIAL.into_iter().filter(|&t| t != "<|en|>").collect();
+        tokenizer_with(&special)
+    }
+
+    fn tokenizer_with(special: &[&str]) -> Tokenizer {
+        let text = ["Ġhello", "Ġwor", "ld", ",", "Ġhow", "Ġare", "Ġyou", "?", "Ġ\"", "hi", "\""];
+        let vocab: serde_json::Map<String, serde_json::Value> = text
+            .iter()
+            .chain(special)
+            .enumerate()
+            .map(|(id, token)| (token.to_string(), id.into()))
+            .collect();
+        let added: Vec<serde_json::Value> = special
+            .iter()
+            .enumerate()
+            .map(|(i, token)| serde_json::json!({
+                "id": text.len() + i, "content": token, "single_word": false, "lstrip": false,
+                "rstrip": false, "normalized": false, "special": true,
+            }))
+            .collect();
+        let json = serde_json::json!({
+            "version": "1.0",
+            "added_tokens": added,
+            "pre_tokenizer": {"type": "ByteLevel", "add_prefix_space": false, "trim_offsets": true, "use_regex": true},
+            "decoder": {"type": "ByteLevel", "add_prefix_space": true, "trim_offsets": true, "use_regex": true},
+            "model": {"type": "WordLevel", "vocab": vocab, "unk_token": "?"},
+        });
+        json.to_string().parse().unwrap()
+    }
+
+    fn ids(tokenizer: &Tokenizer, tokens: &[&str]) -> Vec<u32> {
+        tokens.iter().map(|t| token_id(tokenizer, t).unwrap()).collect()
+    }
+
+    /// A tiny zero-weight model; only `vocab_size` matters to `configure`
+    fn decoder(tokenizer: Tokenizer, vocab_size: usize) -> Decoder {
+        let config = m::Config {
+            num_mel_bins: 80,
+            max_source_positions: 8,
+            d_model: 8,
+            encoder_attention_heads: 1,
+            encoder_layers: 1,
+            vocab_size,
+            max_target_positions: 8,
+            decoder_attention_heads: 1,
+            decoder_layers: 1,
+            suppress_tokens: Vec::new(),
+        };
+        let weights = Weights::Full(VarBuilder::zeros(DType::F32, &Device::Cpu));
+        let model = Whisper::load(&weights, config).unwrap();
+        Decoder::new(model, tokenizer, 0, &Device::Cpu, None, None, true, None, false).unwrap()
+    }
+
+    #[test]
+    fn translate_prefix_depends_on_the_model() {
+        let tokenizer = tokenizer();
+        let en = token_id(&tokenizer, "<|en|>").unwrap();
+        let mut multilingual = decoder(tokenizer.clone(), 51865);
+        multilingual.configure(Some(en), Some(Task::Translate), false);
+        let expected = ids(&tokenizer, &[m::SOT_TOKEN, "<|en|>", m::TRANSLATE_TOKEN, m::NO_TIMESTAMPS_TOKEN]);
+        assert_eq!(multilingual.start_tokens(), (expected, 0));
+
+        // English-only models have no language token and can't translate
+        let tokenizer = english_tokenizer();
+        let mut english = decoder(tokenizer.clone(), 51864);
+        english.configure(None, Some(Task::Translate), false);
+        let expected = ids(&tokenizer, &[m::SOT_TOKEN, m::TRANSCRIBE_TOKEN, m::NO_TIMESTAMPS_TOKEN]);
+        assert_eq!(english.start_tokens(), (expected, 0));
+    }
+
+    #[test]
+    fn words_join_subwords_and_punctuation() {
+        let tokenizer = tokenizer();
+        let tokens = ids(&tokenizer, &["Ġhello", "Ġwor", "ld", ",", "Ġhow", "Ġare", "Ġyou", "?", "Ġ\"", "hi", "\""]);
+        let words = split_words(&tokenizer, &tokens, true).unwrap();
+        let expected = [("hello", 1), ("world,", 3), ("how", 1), ("are", 1), ("you?", 2), ("\"hi\"", 3)];
+        assert_eq!(words, expected.map(|(w, n)| (w.to_string(), n)));
+        assert_eq!(words.iter().map(|(_, n)| n).sum::<usize>(), tokens.len());
+    }
+
+    #[test]
+    fn words_without_spaces_are_single_pieces() {
+        let tokenizer = tokenizer();
+        let tokens = ids(&tokenizer, &["Ġwor", "ld", ","]);
+        let words = split_words(&tokenizer, &tokens, false).unwrap();
+        assert_eq!(words, [("wor".to_string(), 1), ("ld".to_string(), 1), (",".to_string(), 1)]);
+    }
+
+    #[test]
+    fn translation_starts_with_the_translate_token() {
+        let tokenizer = tokenizer();
+        let special = SpecialTokens::new(&tokenizer).unwrap();
+        let en = token_id(&tokenizer, "<|en|>").ok();
+        let (tokens, prompt_len) = initial_tokens(&special, &[], en, Some(Task::Translate), true);
+        assert_eq!(tokens, ids(&tokenizer, &[m::SOT_TOKEN, "<|en|>", m::TRANSLATE_TOKEN]));
+        assert_eq!(prompt_len, 0);
+        let (tokens, _) = initial_tokens(&special, &[], None, None, true);
+        assert_eq!(tokens, ids(&tokenizer, &[m::SOT_TOKEN, m::TRANSCRIBE_TOKEN]));
+    }
+
+    #[test]
+    fn prompt_comes_before_the_start_of_transcript() {
+        let tokenizer = tokenizer();
+        let special = SpecialTokens::new(&tokenizer).unwrap();
+        let prompt = ids(&tokenizer, &["Ġhow", "Ġare"]);
+        let (tokens, prompt_len) = initial_tokens(&special, &prompt, None, Some(Task::Transcribe), true);
+        let expected = ["<|startofprev|>", "Ġhow", "Ġare", m::SOT_TOKEN, m::TRANSCRIBE_TOKEN];
+        assert_eq!(tokens, ids(&tokenizer, &expected));
+        assert_eq!(prompt_len, 3);
+    }
+
+    #[test]
+    fn without_timestamps_no_timestamp_token_can_be_sampled() {
+        let tokenizer = tokenizer();
+        let special = SpecialTokens::new(&tokenizer).unwrap();
+        let (tokens, _) = initial_tokens(&special, &[], None, None, false);
+        assert_eq!(tokens.last(), Some(&special.no_timestamps));
+
+        let vocab_size = tokenizer.get_vocab_size(true);
+        let suppressed = ids(&tokenizer, &["ld"]);
+        let blocked = |mask: &[f32], token: &str| mask[token_id(&tokenizer, token).unwrap() as usize].is_infinite();
+        let mask = suppress_mask(vocab_size, &suppressed, special.no_timestamps, false);
+        assert!(blocked(&mask, "<|0.00|>") && blocked(&mask, "<|0.02|>") && blocked(&mask, "ld"));
+        assert!(!blocked(&mask, m::NO_TIMESTAMPS_TOKEN) && !blocked(&mask, m::EOT_TOKEN) && !blocked(&mask, "Ġhello"));
+
+        let mask = suppress_mask(vocab_size, &suppressed, special.no_timestamps, true);
+        assert!(blocked(&mask, m::NO_TIMESTAMPS_TOKEN) && blocked(&mask, "ld"));
+        assert!(!blocked(&mask, "<|0.00|>") && !blocked(&mask, "<|0.02|>"));
+    }
+
+    #[test]
+    fn repetitive_text_compresses_past_the_threshold() {
+        let looping = "Thank you for watching. ".repeat(8);
+        assert!(compression_ratio(&looping) > COMPRESSION_RATIO_THRESHOLD);
+        let prose = "And so, my fellow Americans: ask not what your country can do for you, \
+                     ask what you can do for your country.";
+        assert!(compression_ratio(prose) < COMPRESSION_RATIO_THRESHOLD);
+        assert_eq!(compression_ratio(""), 0.0);
+    }
+
+    /// Result of a confident decode of `text`
+    fn decoded(text: &str) -> DecodingResult {
+        DecodingResult {
+            tokens: Vec::new(),
+            text: text.to_string(),
+            avg_logprob: -0.2,
+            no_speech_prob: 0.01,
+            temperature: 0.0,
+            compression_ratio: compression_ratio(text),
+        }
+    }
+
+    #[test]
+    fn repetitive_or_unsure_decodes_are_retried() {
+        let thresholds = Thresholds::default();
+        let looping = decoded(&"Thank you for watching. ".repeat(8));
+        assert!(needs_fallback(&looping, &thresholds, false));
+        let prose = decoded("And so, my fellow Americans: ask not what your country can do for you.");
+        assert!(!needs_fallback(&prose, &thresholds, false));
+        assert!(needs_fallback(&prose, &thresholds, true));
+        let unsure = DecodingResult { avg_logprob: -1.5, ..prose.clone() };
+        assert!(needs_fallback(&unsure, &thresholds, false));
+        // Likely silence is kept rather than decoded hotter
+        let silence = DecodingResult { no_speech_prob: 0.9, ..looping };
+        assert!(!needs_fallback(&silence, &thresholds, false));
+    }
+
+    #[test]
+    fn repeated_token_is_flagged_past_the_limit() {
+        assert_eq!(find_repetition(&[7, 9, 9, 9, 9, 9], 4), Some((1, 1)));
+        assert_eq!(find_repetition(&[7, 9, 9, 9, 9], 4), None);
+    }
+
+    #[test]
+    fn repeated_ngram_is_found_at_its_start() {
+        let tokens = [1, 2, 3, 4, 3, 4, 3, 4, 5];
+        assert_eq!(find_repetition(&tokens, 2), Some((2, 2)));
+    }
+
+    #[test]
+    fn zero_limit_disables_the_check() {
+        assert_eq!(find_repetition(&[9; 40], 0), None);
+    }
+}
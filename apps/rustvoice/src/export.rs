@@ -0,0 +1,199 @@
+//! Transcript export formats
+
+use crate::decoder::Segment;
+
+/// Confidence of a segment as the mean per-token probability (0.0 to 1.0)
+pub fn segment_confidence(segment: &Segment) -> f64 {
+    segment.dr.avg_logprob.exp().clamp(0.0, 1.0)
+}
+
+/// Plain-text transcript for proofreading: one line per segment with its
+/// time range and confidence. Segments below `threshold` are wrapped in
+/// `?...?` so they stand out.
+pub fn annotated_text(segments: &[Segment], threshold: f64) -> String {
+    let mut out = String::new();
+    for seg in segments {
+        let text = seg.dr.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let confidence = segment_confidence(seg);
+        let text = if confidence < threshold {
+            format!("?{}?", text)
+        } else {
+            text.to_string()
+        };
+        out.push_str(&format!(
+            "[{} --> {}] ({:.0}%) {}\n",
+            format_timestamp(seg.start),
+            format_timestamp(seg.start + seg.duration),
+            confidence * 100.0,
+            text
+        ));
+    }
+    out
+}
+
+/// Format seconds as `MM:SS.s` (or `H:MM:SS.s` past an hour)
+fn format_timestamp(secs: f64) -> String {
+    let tenths = (secs * 10.0).round() as u64;
+    let (h, m, s, t) = (tenths / 36000, (tenths / 600) % 60, (tenths / 10) % 60, tenths % 10);
+    if h > 0 {
+        format!("{}:{:02}:{:02}.{}", h, m, s, t)
+    } else {
+        format!("{:02}:{:02}.{}", m, s, t)
+    }
+}
+
+/// Plain transcript, one segment per line
+pub fn plain_text(segments: &[Segment]) -> String {
+    let mut out = String::new();
+    for seg in segments {
+        let text = seg.dr.text.trim();
+        if !text.is_empty() {
+            out.push_str(text);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// SubRip subtitles: numbered cues with `HH:MM:SS,mmm` times. Segments
+/// with word timings get one cue per word, since SRT has no inline timing.
+pub fn srt(segments: &[Segment]) -> String {
+    let mut out = String::new();
+    let cues = subtitle_cues(segments).flat_map(|(seg, text)| {
+        if seg.words.is_empty() {
+            vec![(seg.start, seg.start + seg.duration, text)]
+        } else {
+            seg.words.iter().map(|(word, start, end)| (*start, *end, word.clone())).collect()
+        }
+    });
+    for (i, (start, end, text)) in cues.enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_cue_time(start, ','),
+            format_cue_time(end, ','),
+            text
+        ));
+    }
+    out
+}
+
+/// WebVTT subtitles: a header, then cues with `HH:MM:SS.mmm` times. Word
+/// timings become inline `<HH:MM:SS.mmm>` tags for karaoke-style display.
+pub fn vtt(segments: &[Segment]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for (seg, text) in subtitle_cues(segments) {
+        let text = if seg.words.is_empty() {
+            text
+        } else {
+            seg.words
+                .iter()
+                .enumerate()
+                .map(|(i, (word, start, _))| {
+                    if i == 0 {
+                        word.clone()
+                    } else {
+                        format!("<{}>{}", format_cue_time(*start, '.'), word)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            format_cue_time(seg.start, '.'),
+            format_cue_time(seg.start + seg.duration, '.'),
+            text
+        ));
+    }
+    out
+}
+
+/// Segments with text, paired with their trimmed text. A blank line would
+/// end a cue early, so line breaks inside a segment are flattened.
+fn subtitle_cues(segments: &[Segment]) -> impl Iterator<Item = (&Segment, String)> {
+    segments.iter().filter_map(|seg| {
+        let text = seg.dr.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!text.is_empty()).then_some((seg, text))
+    })
+}
+
+/// Format seconds as `HH:MM:SS` plus milliseconds after `separator`
+fn format_cue_time(secs: f64, separator: char) -> String {
+    let millis = (secs.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        (millis / 60_000) % 60,
+        (millis / 1000) % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::DecodingResult;
+
+    fn segment(start: f64, duration: f64, text: &str) -> Segment {
+        Segment {
+            start,
+            duration,
+            dr: DecodingResult {
+                tokens: Vec::new(),
+                text: text.to_string(),
+                avg_logprob: 0.0,
+                no_speech_prob: 0.0,
+                temperature: 0.0,
+                compression_ratio: 1.0,
+            },
+            words: Vec::new(),
+        }
+    }
+
+    fn segments() -> Vec<Segment> {
+        vec![
+            segment(0.5, 2.25, " Hello there."),
+            segment(3723.045, 1.5, " General\nKenobi! "),
+        ]
+    }
+
+    #[test]
+    fn srt_numbers_cues_with_comma_times() {
+        assert_eq!(
+            srt(&segments()),
+            "1\n00:00:00,500 --> 00:00:02,750\nHello there.\n\n\
+             2\n01:02:03,045 --> 01:02:04,545\nGeneral Kenobi!\n\n"
+        );
+    }
+
+    #[test]
+    fn vtt_has_header_and_dot_times() {
+        assert_eq!(
+            vtt(&segments()),
+            "WEBVTT\n\n\
+             00:00:00.500 --> 00:00:02.750\nHello there.\n\n\
+             01:02:03.045 --> 01:02:04.545\nGeneral Kenobi!\n\n"
+        );
+    }
+
+    #[test]
+    fn word_timings_become_cues_and_inline_tags() {
+        let mut seg = segment(1.0, 2.0, " Hello there.");
+        seg.words = vec![("Hello".to_string(), 1.0, 1.4), ("there.".to_string(), 1.6, 2.2)];
+        let segments = [seg];
+        assert_eq!(
+            srt(&segments),
+            "1\n00:00:01,000 --> 00:00:01,400\nHello\n\n\
+             2\n00:00:01,600 --> 00:00:02,200\nthere.\n\n"
+        );
+        assert_eq!(
+            vtt(&segments),
+            "WEBVTT\n\n00:00:01.000 --> 00:00:03.000\nHello <00:00:01.600>there.\n\n"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Global hotkey bindings
+//!
+//! Bindings are stored in the settings as key combos and matched by the
+//! `rdev` listener thread. Keys are named after `rdev::Key`'s variants
+//! (`Escape`, `KeyD`, `Equal`, ...), so any key rdev reports can be bound.
+
+use parking_lot::Mutex;
+use rdev::Key;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+
+/// A key and the modifiers held with it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct KeyCombo {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub key: String, // `rdev::Key` variant name
+}
+
+impl KeyCombo {
+    fn new(ctrl: bool, alt: bool, shift: bool, key: Key) -> Self {
+        Self { ctrl, alt, shift, key: key_name(key) }
+    }
+
+    /// The combo made by pressing `key` with `mods` held
+    pub fn pressed(key: Key, mods: Modifiers) -> Self {
+        Self::new(mods.ctrl, mods.alt, mods.shift, key)
+    }
+
+    /// Whether the `pressed` combo triggers this one. `=` and `-` also
+    /// answer to the keypad's + and - with the same modifiers.
+    pub fn matches(&self, pressed: &KeyCombo) -> bool {
+        let keypad = match self.key.as_str() {
+            "Equal" => "KpPlus",
+            "Minus" => "KpMinus",
+            _ => return self == pressed,
+        };
+        self == pressed || *pressed == KeyCombo { key: keypad.to_string(), ..self.clone() }
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        let key = self.key.as_str();
+        let label = match key {
+            "Escape" => "Esc",
+            "Equal" => "=",
+            "Minus" => "-",
+            "KpPlus" => "Num +",
+            "KpMinus" => "Num -",
+            _ => key
+                .strip_prefix("Key")
+                .or_else(|| key.strip_prefix("Num"))
+                .filter(|rest| rest.chars().count() == 1)
+                .unwrap_or(key),
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// `rdev::Key` variant name, as stored in a `KeyCombo`
+pub fn key_name(key: Key) -> String {
+    format!("{:?}", key)
+}
+
+/// What a hotkey does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    Stop,
+    Pause,
+    SpeedUp,
+    SpeedDown,
+    Dictate,
+}
+
+impl HotkeyAction {
+    pub fn all() -> &'static [HotkeyAction] {
+        &[
+            HotkeyAction::Stop,
+            HotkeyAction::Pause,
+            HotkeyAction::SpeedUp,
+            HotkeyAction::SpeedDown,
+            HotkeyAction::Dictate,
+        ]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::Stop => "Stop typing (double tap)",
+            HotkeyAction::Pause => "Pause/resume typing",
+            HotkeyAction::SpeedUp => "Speed up",
+            HotkeyAction::SpeedDown => "Speed down",
+            HotkeyAction::Dictate => "Dictation",
+        }
+    }
+}
+
+/// Key combos for each global action
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Hotkeys {
+    pub stop: KeyCombo,
+    pub pause: KeyCombo,
+    pub speed_up: KeyCombo,
+    pub speed_down: KeyCombo,
+    pub dictate: KeyCombo,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            stop: KeyCombo::new(false, false, false, Key::Escape),
+            pause: KeyCombo::new(false, false, false, Key::Escape),
+            speed_up: KeyCombo::new(false, true, true, Key::Equal),
+            speed_down: KeyCombo::new(false, true, true, Key::Minus),
+            dictate: KeyCombo::new(true, true, false, Key::KeyD),
+        }
+    }
+}
+
+impl Hotkeys {
+    pub fn get(&self, action: HotkeyAction) -> &KeyCombo {
+        match action {
+            HotkeyAction::Stop => &self.stop,
+            HotkeyAction::Pause => &self.pause,
+            HotkeyAction::SpeedUp => &self.speed_up,
+            HotkeyAction::SpeedDown => &self.speed_down,
+            HotkeyAction::Dictate => &self.dictate,
+        }
+    }
+
+    pub fn get_mut(&mut self, action: HotkeyAction) -> &mut KeyCombo {
+        match action {
+            HotkeyAction::Stop => &mut self.stop,
+            HotkeyAction::Pause => &mut self.pause,
+            HotkeyAction::SpeedUp => &mut self.speed_up,
+            HotkeyAction::SpeedDown => &mut self.speed_down,
+            HotkeyAction::Dictate => &mut self.dictate,
+        }
+    }
+
+    /// Another action already bound to `combo`. Stop and Pause may share a
+    /// key: one tap pauses, a quick second tap stops.
+    pub fn conflict(&self, action: HotkeyAction, combo: &KeyCombo) -> Option<HotkeyAction> {
+        let shareable = |a: HotkeyAction, b: HotkeyAction| {
+            matches!((a, b), (HotkeyAction::Stop, HotkeyAction::Pause) | (HotkeyAction::Pause, HotkeyAction::Stop))
+        };
+        HotkeyAction::all()
+            .iter()
+            .copied()
+            .find(|&other| other != action && !shareable(action, other) && self.get(other) == combo)
+    }
+}
+
+/// Modifier keys currently held, tracked from key events
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl Modifiers {
+    /// Record a press or release; returns false if `key` isn't a modifier
+    pub fn update(&mut self, key: Key, down: bool) -> bool {
+        match key {
+            Key::ControlLeft | Key::ControlRight => self.ctrl = down,
+            Key::Alt | Key::AltGr => self.alt = down,
+            Key::ShiftLeft | Key::ShiftRight => self.shift = down,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Hotkey state shared between the UI and the input listener thread
+#[derive(Default)]
+pub struct SharedHotkeys {
+    pub bindings: Mutex<Hotkeys>,
+    pub capturing: AtomicBool, // The next key press becomes a binding instead of an action
+    pub captured: Mutex<Option<KeyCombo>>,
+    pub dictate_pressed: AtomicBool, // Set on each new press, cleared by the UI
+    pub dictate_held: AtomicBool,    // Whether the dictation combo is held down
+}
+
+impl SharedHotkeys {
+    pub fn new(bindings: Hotkeys) -> Self {
+        Self { bindings: Mutex::new(bindings), ..Default::default() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_keys_answer_to_the_keypad() {
+        let keys = Hotkeys::default();
+        let alt_shift = Modifiers { ctrl: false, alt: true, shift: true };
+        assert!(keys.speed_up.matches(&KeyCombo::pressed(Key::Equal, alt_shift)));
+        assert!(keys.speed_up.matches(&KeyCombo::pressed(Key::KpPlus, alt_shift)));
+        assert!(keys.speed_down.matches(&KeyCombo::pressed(Key::KpMinus, alt_shift)));
+        assert!(!keys.speed_up.matches(&KeyCombo::pressed(Key::KpPlus, Modifiers::default())));
+        assert!(!keys.speed_up.matches(&KeyCombo::pressed(Key::KpMinus, alt_shift)));
+        assert!(!keys.dictate.matches(&KeyCombo::pressed(Key::KpPlus, Modifiers { ctrl: true, alt: true, shift: false })));
+    }
+}
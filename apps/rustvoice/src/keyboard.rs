@@ -0,0 +1,161 @@
+//! Keyboard layouts for Super-Human typos
+//!
+//! Each layout is just its three letter rows. Neighbor keys are worked out
+//! from the row geometry, so adding a layout only needs a new table.
+
+use rand::Rng;
+
+/// Physical key layout typos are drawn from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Qwertz,
+    Azerty,
+    Dvorak,
+}
+
+/// Horizontal offset of each row (top, home, bottom) in key widths
+const ROW_OFFSETS: [f32; 3] = [0.0, 0.25, 0.75];
+
+impl KeyboardLayout {
+    /// Top, home and bottom row keys, left to right
+    fn rows(self) -> [&'static str; 3] {
+        match self {
+            KeyboardLayout::Qwerty => ["qwertyuiop", "asdfghjkl", "zxcvbnm"],
+            KeyboardLayout::Qwertz => ["qwertzuiopü", "asdfghjklöä", "yxcvbnm"],
+            KeyboardLayout::Azerty => ["azertyuiop", "qsdfghjklm", "wxcvbn"],
+            KeyboardLayout::Dvorak => ["',.pyfgcrl", "aoeuidhtns", ";qjkxbmwvz"],
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            KeyboardLayout::Qwerty => "QWERTY",
+            KeyboardLayout::Qwertz => "QWERTZ",
+            KeyboardLayout::Azerty => "AZERTY",
+            KeyboardLayout::Dvorak => "Dvorak",
+        }
+    }
+
+    /// Convert from settings string
+    pub fn from_settings_str(s: &str) -> Self {
+        match s {
+            "qwertz" => KeyboardLayout::Qwertz,
+            "azerty" => KeyboardLayout::Azerty,
+            "dvorak" => KeyboardLayout::Dvorak,
+            _ => KeyboardLayout::Qwerty,
+        }
+    }
+
+    /// Convert to settings string
+    pub fn to_settings_str(self) -> &'static str {
+        match self {
+            KeyboardLayout::Qwerty => "qwerty",
+            KeyboardLayout::Qwertz => "qwertz",
+            KeyboardLayout::Azerty => "azerty",
+            KeyboardLayout::Dvorak => "dvorak",
+        }
+    }
+
+    pub fn all() -> &'static [KeyboardLayout] {
+        &[
+            KeyboardLayout::Qwerty,
+            KeyboardLayout::Qwertz,
+            KeyboardLayout::Azerty,
+            KeyboardLayout::Dvorak,
+        ]
+    }
+
+    /// Letters on the keys touching `c` (lowercase), in row order
+    pub fn neighbors(self, c: char) -> Vec<char> {
+        let rows = self.rows();
+        let Some((row, col)) = rows
+            .iter()
+            .enumerate()
+            .find_map(|(r, keys)| keys.chars().position(|k| k == c).map(|col| (r, col)))
+        else {
+            return Vec::new();
+        };
+        let x = col as f32 + ROW_OFFSETS[row];
+
+        let mut out = Vec::new();
+        for (r, keys) in rows.iter().enumerate() {
+            if r.abs_diff(row) > 1 {
+                continue;
+            }
+            for (i, k) in keys.chars().enumerate() {
+                let dx = (i as f32 + ROW_OFFSETS[r] - x).abs();
+                let adjacent = if r == row { dx == 1.0 } else { dx < 1.0 };
+                if adjacent && k.is_alphabetic() {
+                    out.push(k);
+                }
+            }
+        }
+        out
+    }
+
+    /// Pick a random neighbor key for a typo, keeping the case of `c`.
+    /// A space "slips" into a double space.
+    pub fn random_neighbor(self, c: char) -> Option<char> {
+        if c == ' ' {
+            return Some(' ');
+        }
+        let lower = c.to_lowercase().next()?;
+        let neighbors = self.neighbors(lower);
+        if neighbors.is_empty() {
+            return None;
+        }
+        let n_char = neighbors[rand::thread_rng().gen_range(0..neighbors.len())];
+        if c.is_uppercase() {
+            n_char.to_uppercase().next()
+        } else {
+            Some(n_char)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut keys: Vec<char>) -> Vec<char> {
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn azerty_neighbors_differ_from_qwerty() {
+        assert_eq!(sorted(KeyboardLayout::Qwerty.neighbors('a')), ['q', 's', 'w', 'z']);
+        assert_eq!(sorted(KeyboardLayout::Azerty.neighbors('a')), ['q', 'z']);
+    }
+
+    #[test]
+    fn qwerty_matches_the_old_neighbor_table() {
+        // The hand-written table this layout replaced
+        const OLD_TABLE: &[(char, &str)] = &[
+            ('a', "qwsz"), ('b', "vghn"), ('c', "xdfv"), ('d', "serfcx"), ('e', "wsdr"), ('f', "drtgv"),
+            ('g', "ftyhb"), ('h', "gyunj"), ('i', "ujko"), ('j', "hunik"), ('k', "jiolm"), ('l', "kop"),
+            ('m', "njk"), ('n', "bhjm"), ('o', "iklp"), ('p', "ol"), ('q', "wa"), ('r', "edft"),
+            ('s', "awedxz"), ('t', "rfgy"), ('u', "yhji"), ('v', "cfgb"), ('w', "qase"), ('x', "zsdc"),
+            ('y', "tghu"), ('z', "asx"),
+        ];
+        // The table left out the bottom-row key below these four
+        const ADDED: &[(char, char)] = &[('f', 'c'), ('g', 'v'), ('h', 'b'), ('j', 'm')];
+        for &(key, old) in OLD_TABLE {
+            let mut expected: Vec<char> = old.chars().collect();
+            expected.extend(ADDED.iter().filter(|(k, _)| *k == key).map(|(_, extra)| *extra));
+            assert_eq!(sorted(KeyboardLayout::Qwerty.neighbors(key)), sorted(expected), "neighbors of {key:?}");
+        }
+    }
+
+    #[test]
+    fn space_slips_to_a_space_and_case_is_kept() {
+        for layout in KeyboardLayout::all() {
+            assert_eq!(layout.random_neighbor(' '), Some(' '));
+            let typo = layout.random_neighbor('E').unwrap();
+            assert!(typo.is_uppercase() && layout.neighbors('e').contains(&typo.to_ascii_lowercase()));
+        }
+        assert_eq!(KeyboardLayout::Qwerty.random_neighbor('7'), None);
+    }
+}
@@ -1,35 +1,88 @@
 use eframe::egui;
 use enigo::{Enigo, Key, Keyboard, Direction, Settings};
 use std::sync::{Arc, atomic::{AtomicBool, AtomicUsize, Ordering}};
+use std::path::PathBuf;
 use std::thread;
 use std::time::{Duration, Instant};
 use arboard::Clipboard;
 use parking_lot::Mutex;
 use rand::Rng;
 use crossbeam_channel::{unbounded, Sender, Receiver};
-use rdev::{listen, EventType, Key as RdevKey};
+use rdev::{listen, EventType};
 use rfd::FileDialog;
 
 // Voice transcription modules (Candle)
 mod audio;
 mod model;
 mod decoder;
+mod whisper_model;
+mod timing;
 mod transcribe;
 mod settings;
+mod text;
+mod window;
+mod export;
+mod keyboard;
+mod hotkeys;
+mod cli;
 
-use audio::{AudioCapture, list_input_devices, get_default_input_device_name};
-use model::{ModelManager, WhisperModel};
-use transcribe::{WhisperTranscriber, TranscriptionResult};
+use audio::{AudioCapture, ChannelSelect, list_input_devices, get_default_input_device_name};
+use model::{ModelManager, WhisperModel, DEMO_EXPECTED_TEXT};
+use decoder::{Segment, Task, Thresholds};
+use transcribe::{WhisperTranscriber, TranscriptionResult, TranscriptionSession, TranscribeOptions, LANGUAGES, build_prompt, segments_text};
 use settings::AppSettings;
+use keyboard::KeyboardLayout;
+use hotkeys::{HotkeyAction, KeyCombo, Modifiers, SharedHotkeys};
+use window::{WindowInfo, list_windows};
+
+/// How long the window must stay put before its new geometry is saved
+const GEOMETRY_SAVE_DELAY: Duration = Duration::from_millis(500);
 
 // --- Global Constants ---
-const NEIGHBORS: &[(&str, &str)] = &[
-    ("a", "qwsz"), ("b", "vghn"), ("c", "xdfv"), ("d", "serfcx"), ("e", "wsdr"), ("f", "drtgv"),
-    ("g", "ftyhb"), ("h", "gyunj"), ("i", "ujko"), ("j", "hunik"), ("k", "jiolm"), ("l", "kop"),
-    ("m", "njk"), ("n", "bhjm"), ("o", "iklp"), ("p", "ol"), ("q", "wa"), ("r", "edft"),
-    ("s", "awedxz"), ("t", "rfgy"), ("u", "yhji"), ("v", "cfgb"), ("w", "qase"), ("x", "zsdc"),
-    ("y", "tghu"), ("z", "asx"), (" ", " ")
-];
+/// File extensions offered for audio upload and folder transcription
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "wav", "m4a", "ogg", "flac"];
+
+/// Lowest level the input meter shows
+const METER_FLOOR_DB: f32 = -60.0;
+
+/// Convert a 0-1 amplitude to dBFS, bottoming out at the meter floor
+fn to_dbfs(amplitude: f32) -> f32 {
+    if amplitude <= 0.0 {
+        METER_FLOOR_DB
+    } else {
+        (20.0 * amplitude.log10()).max(METER_FLOOR_DB)
+    }
+}
+
+/// Position of an amplitude on the input meter (0 at the floor, 1 at 0 dBFS)
+fn meter_fraction(amplitude: f32) -> f32 {
+    1.0 - to_dbfs(amplitude) / METER_FLOOR_DB
+}
+
+/// Progress messages from the folder transcription worker
+enum BatchUpdate {
+    FileDone { name: String, text: String },
+    Progress {
+        done_files: usize,
+        total_files: usize,
+        processed_secs: f64,
+        total_secs: f64,
+        rtf: f64, // Processing time / audio time so far
+    },
+    Finished(String),
+}
+
+/// Messages from the model loading thread
+enum ModelLoadUpdate {
+    Download { file: String, downloaded: u64, total: Option<u64> },
+    /// Finished, with the settings the load started from (they may have changed since)
+    Done {
+        result: anyhow::Result<Arc<WhisperTranscriber>>,
+        precision: String,
+        device: String,
+        model_dir: String,
+    },
+}
 
 // --- App State ---
 struct AutoTyperApp {
@@ -41,13 +94,15 @@ struct AutoTyperApp {
     // Config
     settings: AppSettings,
     speed_cpm: Arc<AtomicUsize>,
-    mode: usize, // 0=Natural, 1=SuperHuman, 2=Turbo, 3=Block
+    mode: usize, // 0=Natural, 1=SuperHuman, 2=Turbo, 3=Block, 4=Paste
     
     // Control
     running: Arc<AtomicBool>,
     paused: Arc<AtomicBool>,
     pause_pending: Arc<AtomicBool>,
     stop_requested: Arc<AtomicBool>,
+    hotkeys: Arc<SharedHotkeys>,
+    push_to_talk_active: bool, // Dictation was started by holding the hotkey
     
     // Channels
     status_rx: Receiver<(String, f32, bool)>, // msg, progress, is_paused
@@ -56,23 +111,67 @@ struct AutoTyperApp {
     audio_capture: Option<AudioCapture>,
     transcriber: Option<Arc<WhisperTranscriber>>,
     transcription_rx: Option<Receiver<TranscriptionResult>>,
-    model_load_rx: Option<Receiver<anyhow::Result<Arc<WhisperTranscriber>>>>,
+    transcription_session: Option<TranscriptionSession>,
+    model_load_rx: Option<Receiver<ModelLoadUpdate>>,
+    loaded_model: Option<WhisperModel>,  // Model behind `transcriber`
+    loaded_precision: String,            // Precision `transcriber` was loaded with
+    loaded_device: String,               // Device setting `transcriber` was loaded with
+    loaded_model_dir: String,            // Offline folder `transcriber` was loaded from ("" = downloaded)
+    loading_model: Option<WhisperModel>, // Model being fetched by `model_load_rx`
     is_dictating: bool,
-    mic_muted: bool,
     pending_transcription: String,
+    dictation_anchor: usize,      // Byte offset in `text_to_type` where confirmed text goes next
+    dictation_snapshot: String,   // `text_to_type` as of the last insert, to detect user edits
+    confirmed_seen: String,       // Confirmed text already received this session
+    held_confirmed: String,       // Confirmed text waiting for a sentence boundary
+    inserted_chunks: Vec<(usize, String)>, // Confirmed insertions as (byte offset, text), newest last, for undo
+    transcript_segments: Vec<Segment>, // Finalized segments of the last session, for export
+    
+    // Live typing of dictation (see `settings.live_typing`)
+    live_type_tx: Option<Sender<String>>,
+    live_typed: Arc<AtomicUsize>, // Bytes of the live span the typing thread has typed
+    live_span_start: usize,       // Byte offset in `text_to_type` where the live span begins
+    live_sent: usize,             // Bytes of the live span handed to the typing thread
     model_status: String,
     model_progress: f32,
     
     // File Playback
     file_playback_stop: Arc<AtomicBool>,
+    playback_path: Option<PathBuf>, // File behind `transcript_segments`, for seeking
+    
+    // Folder Transcription
+    batch_rx: Option<Receiver<BatchUpdate>>,
+    batch_stop: Arc<AtomicBool>,
+    batch_progress: f32,
+    batch_status: String,
+    
+    // Typing run queued behind the one in progress (see `settings.overlapping_start`)
+    queued_start: Option<String>,
+    
+    // Annotated transcript export
+    export_rx: Option<Receiver<anyhow::Result<PathBuf>>>,
+    
+    // Cached model file verification
+    verify_rx: Option<Receiver<anyhow::Result<usize>>>,
+    
+    // Demo clip transcription
+    demo_rx: Option<Receiver<anyhow::Result<String>>>,
     
     // UI State
+    geometry_changed_at: Option<Instant>, // Window moved/resized, settings not saved yet
+    binding_action: Option<HotkeyAction>, // Hotkey waiting for a key press to bind
+    hotkey_error: String,
     show_settings: bool,
+    profile_name_input: String,
     selected_model: WhisperModel,
     
     // Audio Device Selection
     available_mics: Vec<String>,
     selected_mic: Option<String>,
+    
+    // Typing Target Window
+    target_windows: Vec<WindowInfo>,
+    target_window: Option<WindowInfo>,
 }
 
 #[derive(Clone)]
@@ -81,12 +180,15 @@ enum AppMode {
     SuperHuman,
     Turbo,
     Block,
+    Paste,
 }
 
 impl AutoTyperApp {
+    #[allow(clippy::too_many_arguments)]
     fn new(cc: &eframe::CreationContext<'_>, status_rx: Receiver<(String, f32, bool)>, 
            running: Arc<AtomicBool>, paused: Arc<AtomicBool>, pause_pending: Arc<AtomicBool>, 
-           stop_requested: Arc<AtomicBool>, speed_cpm: Arc<AtomicUsize>) -> Self {
+           stop_requested: Arc<AtomicBool>, speed_cpm: Arc<AtomicUsize>,
+           hotkeys: Arc<SharedHotkeys>) -> Self {
         
         setup_custom_fonts(&cc.egui_ctx);
         configure_styles(&cc.egui_ctx);
@@ -98,10 +200,11 @@ impl AutoTyperApp {
         
         // Extract model selection before moving settings
         let selected_model = WhisperModel::from_settings_str(&settings.model_size);
+        let status_msg = format!("Ready. Double-tap {} to stop.", settings.hotkeys.stop);
         
         Self {
             text_to_type: String::new(),
-            status_msg: "Ready. Double-Tap ESC to Stop.".to_owned(),
+            status_msg,
             progress: 0.0,
             settings,
             speed_cpm,
@@ -110,23 +213,56 @@ impl AutoTyperApp {
             paused,
             pause_pending,
             stop_requested,
+            hotkeys,
+            push_to_talk_active: false,
             status_rx,
             // Voice transcription (v6)
             audio_capture: None,
             transcriber: None,
             transcription_rx: None,
+            transcription_session: None,
             model_load_rx: None,
+            loaded_model: None,
+            loaded_precision: String::new(),
+            loaded_device: String::new(),
+            loaded_model_dir: String::new(),
+            loading_model: None,
             is_dictating: false,
-            mic_muted: false,
             pending_transcription: String::new(),
+            dictation_anchor: 0,
+            dictation_snapshot: String::new(),
+            confirmed_seen: String::new(),
+            held_confirmed: String::new(),
+            transcript_segments: Vec::new(),
+            live_type_tx: None,
+            live_typed: Arc::new(AtomicUsize::new(0)),
+            inserted_chunks: Vec::new(),
+            live_span_start: 0,
+            live_sent: 0,
             model_status: "Model not loaded".to_string(),
             model_progress: 0.0,
             file_playback_stop: Arc::new(AtomicBool::new(false)),
+            playback_path: None,
+            batch_rx: None,
+            batch_stop: Arc::new(AtomicBool::new(false)),
+            batch_progress: 0.0,
+            batch_status: String::new(),
+            demo_rx: None,
+            export_rx: None,
+            verify_rx: None,
+            queued_start: None,
+            geometry_changed_at: None,
+            binding_action: None,
+            hotkey_error: String::new(),
             show_settings: false,
+            profile_name_input: String::new(),
             selected_model,
             // Audio device selection
             available_mics: list_input_devices(),
             selected_mic: get_default_input_device_name(),
+            // Typing target
+            target_windows: list_windows(),
+            target_window: None,
         }
     }
 
@@ -139,57 +275,351 @@ impl AutoTyperApp {
 
         println!("DEBUG: upload_audio_file called");
         if let Some(path) = FileDialog::new()
-            .add_filter("Audio", &["mp3", "wav", "m4a", "ogg", "flac"])
+            .add_filter("Audio", AUDIO_EXTENSIONS)
             .set_directory("/") // Default to root to ensure it doesn't get stuck? Or just remove set_directory if it exists (it doesn't)
             .pick_file() 
         {
             println!("DEBUG: File selected: {:?}", path);
-            let stop_signal = Arc::new(AtomicBool::new(false));
-            self.file_playback_stop = stop_signal.clone();
-            
-            // Channel for audio chunks
-            let (audio_tx, audio_rx) = unbounded();
+            self.play_audio_file(path, 0.0, Vec::new());
+        }
+    }
+    
+    /// Stream a file through the live transcriber from `start_secs` on.
+    /// `kept` are segments before that point that stay in the transcript.
+    fn play_audio_file(&mut self, path: PathBuf, start_secs: f64, kept: Vec<Segment>) {
+        self.end_transcription_session();
+        let stop_signal = Arc::new(AtomicBool::new(false));
+        self.file_playback_stop = stop_signal.clone();
+        
+        // Channel for audio chunks
+        let (audio_tx, audio_rx) = unbounded();
+        
+        // Spawn file reader thread
+        let path_clone = path.clone();
+        let stop_clone = stop_signal.clone();
+        
+        let chunk_ms = self.settings.chunk_ms.clamp(50, 5000);
+        let skip = (start_secs.max(0.0) * 16000.0) as usize;
+        let channel_select = ChannelSelect::from_settings(self.settings.input_channel);
+        thread::spawn(move || {
+            match audio::decode_audio_file(&path_clone, channel_select) {
+                Ok(samples) => {
+                     let chunk_size = (16 * chunk_ms) as usize; // chunk_ms at 16kHz
+                     for chunk in samples[skip.min(samples.len())..].chunks(chunk_size) {
+                         if stop_clone.load(Ordering::Relaxed) { break; }
+                         if audio_tx.send(chunk.to_vec()).is_err() { break; }
+                         // Real-time simulation, a little faster (96%) to feel snappier
+                         thread::sleep(Duration::from_millis(chunk_ms as u64 * 24 / 25)); 
+                     }
+                }
+                Err(e) => {
+                    log::error!("File decode error: {}", e);
+                }
+            }
+        });
+
+        // Start Transcriber with this RX
+        if let Some(transcriber) = &self.transcriber {
+            let t = transcriber.clone();
+            let (tx, rx) = unbounded();
+            self.transcription_rx = Some(rx);
+            let options = TranscribeOptions { start_secs, ..self.transcribe_options() };
+            self.transcription_session = Some(t.start(audio_rx, tx, options));
+            if self.settings.clear_before_upload {
+                // After a seek, the box keeps only the text before the new position
+                self.text_to_type = segments_text(&kept, 0.0, self.settings.timestamps);
+            }
+            self.begin_transcription_session();
+            self.transcript_segments = kept;
             
-            // Spawn file reader thread
-            let path_clone = path.clone();
-            let stop_clone = stop_signal.clone();
+            self.is_dictating = true;
+            self.status_msg = format!("Playing: {:?}", path.file_name().unwrap_or_default());
+            if start_secs > 0.0 {
+                self.status_msg.push_str(&format!(" from {}", format_timecode(start_secs)));
+            }
+            if let Some(warning) = self.decoding_warning() {
+                self.status_msg.push_str(&format!(" ({})", warning));
+            }
+            self.playback_path = Some(path);
+        }
+    }
+    
+    /// Replay the last file from the start of segment `index`
+    fn seek_playback(&mut self, index: usize) {
+        let (Some(path), Some(segment)) = (self.playback_path.clone(), self.transcript_segments.get(index)) else { return };
+        let start = segment.start;
+        let kept = self.transcript_segments[..index].to_vec();
+        self.play_audio_file(path, start, kept);
+    }
+    
+    /// Transcribe every audio file in a folder, reporting progress and ETA
+    fn transcribe_folder(&mut self) {
+        let Some(transcriber) = self.transcriber.clone() else {
+            self.status_msg = "Model not loaded. Please load model first.".to_string();
+            return;
+        };
+        let Some(dir) = FileDialog::new().pick_folder() else { return; };
+        
+        let mut files: Vec<PathBuf> = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension()
+                    .and_then(|e| e.to_str())
+                    .map(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+                    .unwrap_or(false))
+                .collect(),
+            Err(e) => {
+                self.status_msg = format!("Cannot read folder: {}", e);
+                return;
+            }
+        };
+        if files.is_empty() {
+            self.status_msg = "No audio files found in folder.".to_string();
+            return;
+        }
+        files.sort();
+        
+        let options = self.transcribe_options();
+        let channel_select = ChannelSelect::from_settings(self.settings.input_channel);
+        let stop_signal = Arc::new(AtomicBool::new(false));
+        self.batch_stop = stop_signal.clone();
+        let (tx, rx) = unbounded();
+        self.batch_rx = Some(rx);
+        self.batch_progress = 0.0;
+        self.batch_status = format!("Scanning {} files...", files.len());
+        
+        thread::spawn(move || {
+            // Measure the whole batch up front so the ETA covers every file
+            let mut durations: Vec<f64> = files.iter()
+                .map(|p| audio::probe_duration(p).unwrap_or(0.0))
+                .collect();
+            let mut total_secs: f64 = durations.iter().sum();
+            let started = Instant::now();
+            let mut processed_secs = 0.0;
             
-            thread::spawn(move || {
-                match audio::decode_audio_file(&path_clone) {
+            for (i, path) in files.iter().enumerate() {
+                if stop_signal.load(Ordering::Relaxed) { break; }
+                let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                
+                let text = match audio::decode_audio_file(path, channel_select) {
                     Ok(samples) => {
-                         let chunk_size = 16000 / 2; // 500ms at 16kHz
-                         for chunk in samples.chunks(chunk_size) {
-                             if stop_clone.load(Ordering::Relaxed) { break; }
-                             if audio_tx.send(chunk.to_vec()).is_err() { break; }
-                             // Real-time simulation: Sleep 500ms
-                             // We can go slightly faster (e.g. 0.8x sleep) to feel snappier but let's stick to 1.0x
-                             thread::sleep(Duration::from_millis(480)); 
-                         }
-                    }
-                    Err(e) => {
-                        log::error!("File decode error: {}", e);
+                        // Container metadata can be missing; trust the decoded length
+                        let actual = samples.len() as f64 / 16000.0;
+                        total_secs += actual - durations[i];
+                        durations[i] = actual;
+                        transcriber.transcribe_segment(&samples, &options).unwrap_or_else(|e| format!("[error: {}]", e))
                     }
-                }
-            });
-
-            // Start Transcriber with this RX
-            if let Some(transcriber) = &self.transcriber {
-                let t = transcriber.clone();
-                let (tx, rx) = unbounded();
-                self.transcription_rx = Some(rx);
-                t.start(audio_rx, tx);
+                    Err(e) => format!("[error: {}]", e),
+                };
+                processed_secs += durations[i];
                 
-                self.is_dictating = true;
-                self.status_msg = format!("Playing: {:?}", path.file_name().unwrap_or_default());
+                let rtf = started.elapsed().as_secs_f64() / processed_secs.max(0.001);
+                let _ = tx.send(BatchUpdate::FileDone { name, text });
+                let _ = tx.send(BatchUpdate::Progress {
+                    done_files: i + 1,
+                    total_files: files.len(),
+                    processed_secs,
+                    total_secs,
+                    rtf,
+                });
             }
+            
+            let summary = if stop_signal.load(Ordering::Relaxed) {
+                "Folder transcription cancelled.".to_string()
+            } else {
+                format!("Folder transcription done in {}.", format_duration(started.elapsed().as_secs_f64()))
+            };
+            let _ = tx.send(BatchUpdate::Finished(summary));
+        });
+    }
+    
+    /// Decoding options for a new transcription run
+    fn transcribe_options(&self) -> TranscribeOptions {
+        TranscribeOptions {
+            greedy_only: self.settings.greedy_only,
+            beam_size: self.settings.beam_size,
+            max_repeats: self.settings.max_repeats,
+            thresholds: Thresholds {
+                no_speech: self.settings.no_speech_threshold,
+                logprob: self.settings.logprob_threshold,
+                compression_ratio: self.settings.compression_ratio_threshold,
+            },
+            warmup_secs: self.settings.warmup_secs,
+            normalize: self.settings.normalize_input.clone(),
+            start_temperature: self.settings.start_temperature,
+            retry_empty: self.settings.retry_empty,
+            prompt: build_prompt(&self.settings.initial_prompt, self.settings.punctuation_priming),
+            timestamps: self.settings.timestamps,
+            word_timestamps: self.settings.word_timestamps,
+            task: if self.settings.task == "translate" && !self.translate_unavailable() {
+                Task::Translate
+            } else {
+                Task::Transcribe
+            },
+            language: self.settings.language.clone().filter(|_| !self.language_invalid()),
+            vad_threshold: self.settings.vad_threshold,
+            context_secs: self.settings.context_secs,
+            poll_ms: self.settings.poll_ms,
+            start_secs: 0.0,
         }
     }
     
+    /// Translate is selected but the loaded model is English-only
+    fn translate_unavailable(&self) -> bool {
+        self.settings.task == "translate"
+            && self.transcriber.as_ref().is_some_and(|t| !t.is_multilingual())
+    }
+    
+    /// A language is pinned that the loaded multilingual model has no token for
+    fn language_invalid(&self) -> bool {
+        match (&self.settings.language, &self.transcriber) {
+            (Some(code), Some(t)) => t.is_multilingual() && t.language_token(code).is_none(),
+            _ => false,
+        }
+    }
+    
+    /// Settings the loaded model can't honor, shown next to the status
+    fn decoding_warning(&self) -> Option<String> {
+        if self.translate_unavailable() {
+            Some("English-only model can't translate, transcribing".to_string())
+        } else if self.language_invalid() {
+            Some(format!(
+                "unknown language '{}', auto-detecting",
+                self.settings.language.as_deref().unwrap_or_default()
+            ))
+        } else {
+            None
+        }
+    }
+    
+    /// Start typing `text` into the target, the single entry point for every start trigger
+    ///
+    /// Only one typing thread may run at a time. A start while one is running
+    /// is rejected or queued depending on `settings.overlapping_start`.
+    fn request_start(&mut self, text: String) {
+        let started = start_typing_thread(
+            text.clone(),
+            self.mode,
+            self.speed_cpm.clone(),
+            self.running.clone(),
+            self.paused.clone(),
+            self.pause_pending.clone(),
+            self.stop_requested.clone(),
+            self.status_rx.clone(), // This is wrong, need Sender. Creating channel in main.
+            self.target_window.as_ref().map(|w| w.id),
+            self.settings.clone(),
+        );
+        if started {
+            return;
+        }
+        
+        if self.settings.overlapping_start == "queue" {
+            self.queued_start = Some(text);
+            self.status_msg = "Already typing; this run will start when the current one ends.".to_string();
+        } else {
+            self.status_msg = "Already typing; START ignored. Stop the current run first.".to_string();
+        }
+    }
+    
+    /// Transcribe an audio file and save a transcript with low-confidence segments marked
+    fn export_annotated(&mut self) {
+        let Some(transcriber) = self.transcriber.clone() else {
+            self.status_msg = "Model not loaded. Please load model first.".to_string();
+            return;
+        };
+        let Some(audio_path) = FileDialog::new().add_filter("Audio", AUDIO_EXTENSIONS).pick_file() else { return; };
+        let default_name = format!(
+            "{}.annotated.txt",
+            audio_path.file_stem().unwrap_or_default().to_string_lossy()
+        );
+        let Some(out_path) = FileDialog::new()
+            .add_filter("Text", &["txt"])
+            .set_file_name(default_name)
+            .save_file()
+        else { return; };
+        
+        let options = self.transcribe_options();
+        let channel_select = ChannelSelect::from_settings(self.settings.input_channel);
+        let threshold = self.settings.low_confidence as f64;
+        let (tx, rx) = unbounded();
+        self.export_rx = Some(rx);
+        self.status_msg = format!("Transcribing {:?} for export...", audio_path.file_name().unwrap_or_default());
+        
+        thread::spawn(move || {
+            let result = audio::decode_audio_file(&audio_path, channel_select)
+                .and_then(|samples| transcriber.transcribe_segments(&samples, &options))
+                .and_then(|segments| {
+                    std::fs::write(&out_path, export::annotated_text(&segments, threshold))?;
+                    Ok(out_path)
+                });
+            let _ = tx.send(result);
+        });
+    }
+    
+    /// Save the last session's transcript as TXT, or as SRT/VTT subtitles with timestamps on
+    fn export_transcript(&mut self) {
+        let mut dialog = FileDialog::new().add_filter("Text", &["txt"]);
+        let subtitles = self.settings.timestamps || self.settings.word_timestamps;
+        if subtitles {
+            dialog = dialog.add_filter("SubRip subtitles", &["srt"]).add_filter("WebVTT subtitles", &["vtt"]);
+        }
+        let Some(mut path) = dialog.set_file_name("transcript.txt").save_file() else { return; };
+        
+        let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+        let content = match extension.as_deref() {
+            Some("srt") if subtitles => export::srt(&self.transcript_segments),
+            Some("vtt") if subtitles => export::vtt(&self.transcript_segments),
+            Some("txt") => export::plain_text(&self.transcript_segments),
+            _ => {
+                path.set_extension("txt");
+                export::plain_text(&self.transcript_segments)
+            }
+        };
+        self.status_msg = match std::fs::write(&path, content) {
+            Ok(()) => format!("Exported transcript to {}", path.display()),
+            Err(e) => format!("Export failed: {}", e),
+        };
+    }
+    
+    /// Transcribe the demo clip to check that the model works end to end
+    fn run_demo(&mut self) {
+        let Some(transcriber) = self.transcriber.clone() else {
+            self.status_msg = "Model not loaded. Please load model first.".to_string();
+            return;
+        };
+        
+        // Plain text so the result can be compared word for word
+        let options = TranscribeOptions { timestamps: false, ..self.transcribe_options() };
+        let hf_token = self.hf_token();
+        let (tx, rx) = unbounded();
+        self.demo_rx = Some(rx);
+        self.status_msg = "Running demo clip...".to_string();
+        
+        thread::spawn(move || {
+            let result = ModelManager::new(hf_token)
+                .and_then(|manager| manager.fetch_demo_audio())
+                .and_then(|path| audio::decode_audio_file(&path, ChannelSelect::Mix))
+                .and_then(|samples| transcriber.transcribe_segment(&samples, &options));
+            let _ = tx.send(result);
+        });
+    }
+    
     /// Start voice dictation
     fn start_dictation(&mut self) {
+        self.end_transcription_session();
+        self.playback_path = None;
+        
         // Initialize audio capture
         match AudioCapture::new() {
             Ok(mut capture) => {
+                capture.set_resampler_chunk(self.settings.resampler_chunk);
+                capture.set_chunk_ms(self.settings.chunk_ms);
+                capture.set_channel_select(ChannelSelect::from_settings(self.settings.input_channel));
+                capture.set_gain(self.settings.input_gain_db, self.settings.auto_gain);
+                if self.settings.save_recordings {
+                    let name = format!("dictation-{}.wav", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+                    capture.set_recording_path(AppSettings::recordings_dir().map(|dir| dir.join(name)));
+                }
                 let mic_name = self.selected_mic.as_deref();
                 if let Err(e) = capture.start_with_device(mic_name) {
                     self.status_msg = format!("Audio error: {}", e);
@@ -206,10 +636,14 @@ impl AutoTyperApp {
                     let (tx, rx) = unbounded();
                     self.transcription_rx = Some(rx);
                     
-                    t.start(audio_rx, tx);
+                    self.transcription_session = Some(t.start(audio_rx, tx, self.transcribe_options()));
+                    self.begin_transcription_session();
                     
                     self.is_dictating = true;
-                    self.status_msg = "🎙 Listening...".to_string();
+                    self.status_msg = match self.decoding_warning() {
+                        Some(warning) => format!("🎙 Listening... ({})", warning),
+                        None => "🎙 Listening...".to_string(),
+                    };
                 } else {
                     self.status_msg = "Model not loaded. Click 'Load Model' first.".to_string();
                     return;
@@ -223,68 +657,364 @@ impl AutoTyperApp {
         }
     }
     
+    /// Tear down the previous dictation/playback session so a new one can't
+    /// receive its results. Text it had already confirmed is kept.
+    fn end_transcription_session(&mut self) {
+        if let Some(mut capture) = self.audio_capture.take() {
+            capture.stop();
+        }
+        self.file_playback_stop.store(true, Ordering::Relaxed);
+        if let Some(session) = self.transcription_session.take() {
+            session.stop();
+        }
+        self.transcription_rx = None;
+        self.finish_transcription_output();
+    }
+    
+    /// Release held text and let the live typing thread finish
+    fn finish_transcription_output(&mut self) {
+        if !self.held_confirmed.is_empty() {
+            let rest = std::mem::take(&mut self.held_confirmed);
+            self.insert_confirmed(&rest);
+        }
+        self.live_type_tx = None;
+        self.pending_transcription.clear();
+    }
+    
+    /// Reset the insertion anchor so a new session's text goes after what is in the box
+    fn begin_transcription_session(&mut self) {
+        self.dictation_anchor = self.text_to_type.len();
+        self.dictation_snapshot = self.text_to_type.clone();
+        self.confirmed_seen.clear();
+        self.held_confirmed.clear();
+        self.transcript_segments.clear();
+        
+        self.live_type_tx = None;
+        self.live_span_start = self.dictation_anchor;
+        self.live_sent = 0;
+        if self.settings.live_typing {
+            let typed = Arc::new(AtomicUsize::new(0));
+            self.live_typed = typed.clone();
+            match start_live_typing_thread(
+                self.running.clone(),
+                self.paused.clone(),
+                self.stop_requested.clone(),
+                typed,
+                self.target_window.as_ref().map(|w| w.id),
+                self.settings.clone(),
+            ) {
+                Some(tx) => self.live_type_tx = Some(tx),
+                None => self.status_msg = "Typing already in progress; live typing is off for this session.".to_string(),
+            }
+        }
+    }
+    
+    /// Queue newly confirmed text, releasing it to the box whole sentences at a time
+    /// when commit-on-punctuation is enabled
+    fn queue_confirmed(&mut self, new_text: &str) {
+        self.held_confirmed.push_str(new_text);
+        
+        let release = if !self.settings.commit_on_punctuation
+            || self.held_confirmed.chars().count() >= self.settings.commit_max_chars
+        {
+            self.held_confirmed.len()
+        } else {
+            text::sentence_boundary(&self.held_confirmed).unwrap_or(0)
+        };
+        
+        if release > 0 {
+            let ready: String = self.held_confirmed.drain(..release).collect();
+            self.insert_confirmed(&ready);
+        }
+    }
+    
+    /// Insert newly confirmed text at the anchor (or the end, per settings)
+    fn insert_confirmed(&mut self, new_text: &str) {
+        // Follow any edits made to the box since the last insert
+        self.follow_box_edits();
+        
+        let at_end = self.settings.dictation_insert == "end" || self.dictation_anchor >= self.text_to_type.len();
+        let tidied;
+        let new_text = if self.settings.tidy_transcript {
+            let preceding = if at_end { &self.text_to_type[..] } else { &self.text_to_type[..self.dictation_anchor] };
+            tidied = text::tidy_transcript(new_text, preceding, self.settings.language.as_deref());
+            &tidied[..]
+        } else {
+            new_text
+        };
+        if new_text.is_empty() {
+            return;
+        }
+        
+        if at_end {
+            self.inserted_chunks.push((self.text_to_type.len(), new_text.to_string()));
+            self.text_to_type.push_str(new_text);
+            self.dictation_anchor = self.text_to_type.len();
+        } else {
+            self.inserted_chunks.push((self.dictation_anchor, new_text.to_string()));
+            self.text_to_type.insert_str(self.dictation_anchor, new_text);
+            self.dictation_anchor += new_text.len();
+        }
+        self.dictation_snapshot = self.text_to_type.clone();
+        
+        if let Some(tx) = &self.live_type_tx {
+            if tx.send(new_text.to_string()).is_ok() {
+                self.live_sent += new_text.len();
+            }
+        }
+    }
+    
+    /// Shift the dictation offsets to account for edits made to the box since the last insert
+    fn follow_box_edits(&mut self) {
+        if self.text_to_type != self.dictation_snapshot {
+            self.dictation_anchor = text::shift_offset(&self.dictation_snapshot, &self.text_to_type, self.dictation_anchor);
+            self.live_span_start = text::shift_offset(&self.dictation_snapshot, &self.text_to_type, self.live_span_start);
+            for (start, _) in &mut self.inserted_chunks {
+                *start = text::shift_offset(&self.dictation_snapshot, &self.text_to_type, *start);
+            }
+            self.dictation_snapshot = self.text_to_type.clone();
+        }
+    }
+    
+    /// Remove the most recently inserted confirmed text from the box. A chunk
+    /// that was since edited in the box is left alone (and forgotten).
+    fn undo_last_insertion(&mut self) {
+        self.follow_box_edits();
+        let Some((start, chunk)) = self.inserted_chunks.pop() else {
+            self.status_msg = "Nothing to undo.".to_string();
+            return;
+        };
+        if !self.text_to_type.get(start..).is_some_and(|rest| rest.starts_with(&chunk)) {
+            self.status_msg = "The last transcription was edited in the box; not undone.".to_string();
+            return;
+        }
+        
+        let end = start + chunk.len();
+        self.text_to_type.replace_range(start..end, "");
+        // Pull offsets after the removed text back by its length
+        let shift = |offset: usize| if offset >= end { offset - chunk.len() } else { offset.min(start) };
+        self.dictation_anchor = shift(self.dictation_anchor);
+        self.live_span_start = shift(self.live_span_start);
+        for (offset, _) in &mut self.inserted_chunks {
+            *offset = shift(*offset);
+        }
+        self.dictation_snapshot = self.text_to_type.clone();
+        self.status_msg = format!("Undid \"{}\"", chunk.trim());
+    }
+    
+    /// Whether dictation is running with the mic paused
+    fn mic_paused(&self) -> bool {
+        self.audio_capture.as_ref().is_some_and(|c| c.is_paused())
+    }
+    
+    /// Pause or resume the mic. The stream and model stay live, so resuming is
+    /// instant; text heard before the pause is confirmed and kept.
+    fn toggle_mic_pause(&mut self) {
+        let Some(capture) = &self.audio_capture else { return; };
+        let pause = !capture.is_paused();
+        capture.set_paused(pause);
+        self.status_msg = if pause { "⏸ Mic paused.".to_string() } else { "🎙 Listening...".to_string() };
+    }
+    
     /// Stop voice dictation or file playback
     fn stop_dictation(&mut self) {
         // Stop Mic
+        let mut saved_recording = None;
         if let Some(mut capture) = self.audio_capture.take() {
+            saved_recording = capture.recording_path().map(|p| p.to_path_buf());
             capture.stop();
         }
         // Stop File
         self.file_playback_stop.store(true, Ordering::Relaxed);
         
-        // Transcriber thread stops when channel disconnects (audio_rx dropped)
+        // Transcriber thread makes a final decode of what it has, then disconnects;
+        // held text and live typing are wrapped up when that happens
         self.is_dictating = false;
-        self.pending_transcription.clear();
-        self.status_msg = "Dictation/Playback stopped.".to_string();
+        self.status_msg = match saved_recording {
+            Some(path) => format!("Dictation stopped. Recording saved to {}", path.display()),
+            None => "Dictation/Playback stopped.".to_string(),
+        };
+    }
+    
+    /// Start/stop dictation from the global hotkey according to `settings.dictation_hotkey`
+    fn handle_dictation_hotkey(&mut self) {
+        let pressed = self.hotkeys.dictate_pressed.swap(false, Ordering::Relaxed);
+        match self.settings.dictation_hotkey.as_str() {
+            "toggle" if pressed => {
+                if self.is_dictating {
+                    self.stop_dictation();
+                } else {
+                    self.start_dictation();
+                }
+            }
+            "push_to_talk" => {
+                // Start on the press edge only, so a failed start isn't retried while held;
+                // a press and release between two frames still starts and then stops
+                if pressed && !self.is_dictating {
+                    self.start_dictation();
+                    self.push_to_talk_active = self.is_dictating;
+                } else if self.push_to_talk_active && !self.hotkeys.dictate_held.load(Ordering::Relaxed) {
+                    self.push_to_talk_active = false;
+                    if self.is_dictating {
+                        self.stop_dictation();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    /// Begin or cancel waiting for the key combo to bind to `action`
+    fn capture_hotkey(&mut self, action: Option<HotkeyAction>) {
+        self.binding_action = action;
+        self.hotkey_error.clear();
+        *self.hotkeys.captured.lock() = None;
+        self.hotkeys.capturing.store(action.is_some(), Ordering::Relaxed);
+    }
+    
+    /// Bind the captured key combo, unless another action already uses it
+    fn finish_hotkey_capture(&mut self) {
+        let Some(action) = self.binding_action else { return; };
+        let Some(combo) = self.hotkeys.captured.lock().take() else { return; };
+        self.binding_action = None;
+        if let Some(other) = self.settings.hotkeys.conflict(action, &combo) {
+            self.hotkey_error = format!("{} is already used for \"{}\".", combo, other.label());
+            return;
+        }
+        *self.settings.hotkeys.get_mut(action) = combo;
+        self.apply_hotkeys();
+    }
+    
+    /// Save the bindings and hand them to the input listener
+    fn apply_hotkeys(&mut self) {
+        self.settings.save();
+        *self.hotkeys.bindings.lock() = self.settings.hotkeys.clone();
+    }
+    
+    /// Remember the window's position and size, saving once it stops changing
+    fn track_window_geometry(&mut self, ctx: &egui::Context) {
+        let (outer, inner, normal) = ctx.input(|i| {
+            let v = i.viewport();
+            let normal = !v.minimized.unwrap_or(false) && !v.maximized.unwrap_or(false) && !v.fullscreen.unwrap_or(false);
+            (v.outer_rect, v.inner_rect, normal)
+        });
+        // Maximized geometry isn't worth restoring; Wayland reports no position
+        let (Some(outer), Some(inner), true) = (outer, inner, normal) else { return; };
+        let pos = [outer.min.x, outer.min.y];
+        let size = [inner.width(), inner.height()];
+        if self.settings.window_pos != Some(pos) || self.settings.window_size != size {
+            self.settings.window_pos = Some(pos);
+            self.settings.window_size = size;
+            self.geometry_changed_at = Some(Instant::now());
+        } else if self.geometry_changed_at.is_some_and(|t| t.elapsed() >= GEOMETRY_SAVE_DELAY) {
+            self.geometry_changed_at = None;
+            self.settings.save();
+        }
+    }
+    
+    /// Whether the selected model, its source, precision or device differs from what is loaded
+    fn needs_model_load(&self) -> bool {
+        self.loaded_model != Some(self.selected_model)
+            || self.loaded_precision != self.settings.precision
+            || self.loaded_device != self.settings.device
+            || self.loaded_model_dir != self.settings.model_dir
+    }
+    
+    /// Whether a background job is running whose progress the UI should show live
+    fn has_pending_work(&self) -> bool {
+        self.is_dictating
+            || self.transcription_rx.is_some()
+            || self.running.load(Ordering::Relaxed)
+            || self.model_load_rx.is_some()
+            || self.batch_rx.is_some()
+            || self.demo_rx.is_some()
+            || self.export_rx.is_some()
+            || self.verify_rx.is_some()
+            || self.queued_start.is_some()
+            || self.binding_action.is_some()
+    }
+    
+    /// Token from the settings, if one is entered
+    fn hf_token(&self) -> Option<String> {
+        Some(self.settings.hf_token.trim().to_string()).filter(|t| !t.is_empty())
+    }
+    
+    /// Offline model folder from the settings, if one is entered
+    fn model_dir(&self) -> Option<PathBuf> {
+        Some(self.settings.model_dir.trim()).filter(|d| !d.is_empty()).map(PathBuf::from)
+    }
+    
+    /// Re-check the selected model's cached files in the background
+    fn verify_model_cache(&mut self) {
+        let model = self.selected_model;
+        let hf_token = self.hf_token();
+        let (tx, rx) = unbounded();
+        self.verify_rx = Some(rx);
+        self.status_msg = format!("Verifying {}...", model.display_name());
+        thread::spawn(move || {
+            let _ = tx.send(ModelManager::new(hf_token).and_then(|manager| manager.verify_cache(model)));
+        });
+    }
+    
+    /// Select a model and load it straight away
+    fn switch_model(&mut self, model: WhisperModel) {
+        self.selected_model = model;
+        self.settings.model_size = model.to_settings_str().to_string();
+        self.settings.save();
+        self.load_model();
     }
     
     /// Load the Whisper model
+    ///
+    /// Any currently loaded model stays active until the new one is ready.
     fn load_model(&mut self) {
-        if self.transcriber.is_some() { return; }
+        if !self.needs_model_load() || self.model_load_rx.is_some() { return; }
         
         let selected = self.selected_model;
-        self.model_status = format!("Downloading {}...", selected.display_name());
+        let precision = self.settings.precision.clone();
+        let device = self.settings.device.clone();
+        let hf_token = self.hf_token();
+        let model_dir = self.model_dir();
+        self.model_status = if model_dir.is_some() {
+            format!("Loading {} from local folder...", selected.display_name())
+        } else {
+            format!("Downloading {}...", selected.display_name())
+        };
         self.model_progress = 0.0;
         
         let (tx, rx) = unbounded();
         self.model_load_rx = Some(rx);
+        self.loading_model = Some(selected);
 
+        let model_dir_setting = self.settings.model_dir.clone();
         thread::spawn(move || {
-            let manager = match ModelManager::new() {
-                Ok(m) => m,
-                Err(e) => {
-                    let _ = tx.send(Err(anyhow::anyhow!("Manager init failed: {}", e)));
-                    return;
-                }
-            };
-            
-            // Fetch Model (using selected model)
-            let model_paths = match manager.fetch_model(selected) {
-                Ok(p) => p,
-                Err(e) => {
-                    let _ = tx.send(Err(anyhow::anyhow!("Download failed: {}", e)));
-                    return;
-                }
-            };
-            
-            // Fetch Mel Filters (80 bins for standard models, 128 for large-v3 if added)
-            let mel_paths = match manager.fetch_mel_filters(80) {
-                 Ok(p) => p,
-                 Err(e) => {
-                     let _ = tx.send(Err(anyhow::anyhow!("Mel filter download failed: {}", e)));
-                     return;
-                 }
-            };
+            let result = (|| -> anyhow::Result<Arc<WhisperTranscriber>> {
+                let mut manager = ModelManager::new(hf_token)
+                    .map_err(|e| anyhow::anyhow!("Manager init failed: {}", e))?;
+                // Offline errors already say which local files are missing or wrong
+                let fetch = if model_dir.is_some() { "Local model load" } else { "Download" };
+                manager.set_local_dir(model_dir);
+                let progress_tx = tx.clone();
+                manager.set_progress(move |file, downloaded, total| {
+                    let _ = progress_tx.send(ModelLoadUpdate::Download { file: file.to_string(), downloaded, total });
+                });
 
-            // Load Transcriber
-            match WhisperTranscriber::new(model_paths, mel_paths) {
-                Ok(t) => {
-                    let _ = tx.send(Ok(Arc::new(t)));
-                }
-                Err(e) => {
-                    let _ = tx.send(Err(anyhow::anyhow!("Load failed: {}", e)));
-                }
-            }
+                // Fetch Model (using selected model)
+                let model_paths = manager
+                    .fetch_model(selected)
+                    .map_err(|e| anyhow::anyhow!("{} failed: {}", fetch, e))?;
+
+                // Fetch Mel Filters (80 bins for most models, 128 for large-v3)
+                let mel_paths = manager
+                    .fetch_mel_filters(selected.num_mel_bins())
+                    .map_err(|e| anyhow::anyhow!("Mel filter {} failed: {}", fetch.to_lowercase(), e))?;
+
+                // Load Transcriber
+                WhisperTranscriber::new(model_paths, mel_paths, &precision, &device)
+                    .map(Arc::new)
+                    .map_err(|e| anyhow::anyhow!("Load failed: {}", e))
+            })();
+            let _ = tx.send(ModelLoadUpdate::Done { result, precision, device, model_dir: model_dir_setting });
         });
     }
 }
@@ -298,46 +1028,207 @@ impl eframe::App for AutoTyperApp {
         }
         
         // Process model loading updates
+        let mut load_result = None;
         if let Some(rx) = &self.model_load_rx {
-             if let Ok(result) = rx.try_recv() {
-                 match result {
-                     Ok(transcriber) => {
-                          let device = transcriber.get_device_name();
-                          self.transcriber = Some(transcriber);
-                          self.model_status = format!("Model Ready (Candle 🕯️) on {}", device);
-                         self.model_progress = 1.0;
-                         self.status_msg = "Model loaded successfully.".to_string();
-                         self.model_load_rx = None; // Done
-                     }
-                     Err(e) => {
-                         self.model_status = format!("Error: {}", e);
-                         self.status_msg = format!("Model load failed: {}", e);
-                         self.model_load_rx = None; // Done
+            while let Ok(update) = rx.try_recv() {
+                match update {
+                    ModelLoadUpdate::Download { file, downloaded, total } => {
+                        const MB: f64 = 1024.0 * 1024.0;
+                        match total {
+                            Some(total) if total > 0 => {
+                                self.model_progress = (downloaded as f64 / total as f64) as f32;
+                                self.model_status = format!(
+                                    "Downloading {}: {:.1} / {:.1} MB",
+                                    file, downloaded as f64 / MB, total as f64 / MB
+                                );
+                            }
+                            _ => {
+                                self.model_status = format!("Downloading {}: {:.1} MB", file, downloaded as f64 / MB);
+                            }
+                        }
+                    }
+                    ModelLoadUpdate::Done { result, precision, device, model_dir } => {
+                        load_result = Some((result, precision, device, model_dir));
+                        break;
+                    }
+                }
+            }
+        }
+        if let Some((result, precision, device_setting, model_dir)) = load_result {
+            match result {
+                Ok(transcriber) => {
+                     let device = transcriber.get_device_name();
+                     // Swap in the new model; a running session keeps its own handle to the old one
+                     self.transcriber = Some(transcriber);
+                     self.loaded_model = self.loading_model.take();
+                     self.loaded_precision = precision;
+                     self.loaded_device = device_setting;
+                     self.loaded_model_dir = model_dir;
+                     if let Some(model) = self.loaded_model {
+                         self.settings.remember_model(model.to_settings_str());
+                         self.settings.save();
                      }
-                 }
-             }
+                     self.model_status = format!("Model Ready (Candle 🕯️) on {}", device);
+                    self.model_progress = 1.0;
+                    self.status_msg = "Model loaded successfully.".to_string();
+                    self.model_load_rx = None; // Done
+                }
+                Err(e) => {
+                    self.loading_model = None;
+                    self.model_load_rx = None; // Done
+                    if let Some(previous) = self.loaded_model {
+                        // Revert the selection to the model that is still loaded
+                        self.selected_model = previous;
+                        self.settings.model_size = previous.to_settings_str().to_string();
+                        self.settings.precision = self.loaded_precision.clone();
+                        self.settings.device = self.loaded_device.clone();
+                        self.settings.model_dir = self.loaded_model_dir.clone();
+                        self.settings.save();
+                        self.model_status = format!("Error: {}. Still using {}", e, previous.display_name());
+                    } else {
+                        self.model_status = format!("Error: {}", e);
+                    }
+                    self.status_msg = format!("Model load failed: {}", e);
+                }
+            }
+        }
+        
+        // Process folder transcription progress
+        if let Some(rx) = &self.batch_rx {
+            let mut finished = None;
+            while let Ok(update) = rx.try_recv() {
+                match update {
+                    BatchUpdate::FileDone { name, text } => {
+                        if !self.text_to_type.is_empty() && !self.text_to_type.ends_with('\n') {
+                            self.text_to_type.push('\n');
+                        }
+                        self.text_to_type.push_str(&format!("[{}]\n{}\n", name, text));
+                    }
+                    BatchUpdate::Progress { done_files, total_files, processed_secs, total_secs, rtf } => {
+                        self.batch_progress = if total_secs > 0.0 {
+                            (processed_secs / total_secs) as f32
+                        } else {
+                            done_files as f32 / total_files as f32
+                        };
+                        let remaining = (total_secs - processed_secs).max(0.0) * rtf;
+                        self.batch_status = format!(
+                            "File {}/{} · RTF {:.2} · about {} remaining",
+                            done_files, total_files, rtf, format_duration(remaining)
+                        );
+                    }
+                    BatchUpdate::Finished(summary) => finished = Some(summary),
+                }
+            }
+            if let Some(summary) = finished {
+                self.batch_rx = None;
+                self.batch_progress = 1.0;
+                self.batch_status.clear();
+                self.status_msg = summary;
+            }
+        }
+        
+        // Start a queued run once the previous one has finished
+        if !self.running.load(Ordering::Relaxed) {
+            if let Some(text) = self.queued_start.take() {
+                self.request_start(text);
+            }
+        }
+        
+        // Process annotated export result
+        if let Some(rx) = &self.export_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.export_rx = None;
+                self.status_msg = match result {
+                    Ok(path) => format!("Exported annotated transcript to {}", path.display()),
+                    Err(e) => format!("Export failed: {}", e),
+                };
+            }
+        }
+        
+        // Process cache verification result
+        if let Some(rx) = &self.verify_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.verify_rx = None;
+                self.status_msg = match result {
+                    Ok(0) => "No cached files for this model yet.".to_string(),
+                    Ok(n) => format!("Verified {} cached model files.", n),
+                    Err(e) => format!("Verification failed: {}", e),
+                };
+            }
+        }
+        
+        // Process demo clip result
+        if let Some(rx) = &self.demo_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.demo_rx = None;
+                match result {
+                    Ok(text) => {
+                        let score = word_match_ratio(DEMO_EXPECTED_TEXT, &text);
+                        self.status_msg = if score >= 0.8 {
+                            format!("✅ Demo OK ({:.0}% of words matched). Your setup works!", score * 100.0)
+                        } else {
+                            format!("⚠ Demo output differs from expected ({:.0}% of words matched).", score * 100.0)
+                        };
+                        if !self.text_to_type.is_empty() && !self.text_to_type.ends_with('\n') {
+                            self.text_to_type.push('\n');
+                        }
+                        self.text_to_type.push_str(&format!("[demo]\n{}\n[expected]\n{}\n", text, DEMO_EXPECTED_TEXT));
+                    }
+                    Err(e) => self.status_msg = format!("Demo failed: {}", e),
+                }
+            }
+        }
+        
+        if !self.show_settings && self.binding_action.is_some() {
+            self.capture_hotkey(None); // Settings closed while waiting for a key
+        }
+        self.finish_hotkey_capture();
+        self.handle_dictation_hotkey();
+        self.track_window_geometry(ctx);
+        
+        // Auto-stop dictation when nobody has spoken for a while
+        if self.is_dictating && self.settings.silence_timeout_secs > 0 && !self.mic_paused() {
+            if let Some(capture) = &self.audio_capture {
+                let timeout = Duration::from_secs(self.settings.silence_timeout_secs as u64);
+                if capture.silence_duration() >= timeout {
+                    self.stop_dictation();
+                    self.status_msg = format!("⏱ Dictation auto-stopped after {}s without speech.", self.settings.silence_timeout_secs);
+                }
+            }
         }
         
         // Process transcription results (but not when muted)
+        let mut results: Vec<TranscriptionResult> = Vec::new();
+        let mut session_ended = false;
         if let Some(rx) = &self.transcription_rx {
-            while let Ok(result) = rx.try_recv() {
-                // Skip updating text if muted
-                if self.mic_muted {
-                    continue;
-                }
-                
-                // Append confirmed text to text_to_type
-                if !result.confirmed.is_empty() && !self.text_to_type.ends_with(&result.confirmed) {
-                    // Find new confirmed text
-                    let existing_len = self.text_to_type.len();
-                    if result.confirmed.len() > existing_len {
-                        let new_text = &result.confirmed[existing_len..];
-                        self.text_to_type.push_str(new_text);
+            loop {
+                match rx.try_recv() {
+                    Ok(result) => results.push(result),
+                    Err(crossbeam_channel::TryRecvError::Empty) => break,
+                    Err(crossbeam_channel::TryRecvError::Disconnected) => {
+                        session_ended = true;
+                        break;
                     }
                 }
-                self.pending_transcription = result.pending;
             }
         }
+        for result in results {
+            // Insert only the part of the confirmed text we haven't inserted yet
+            if result.confirmed != self.confirmed_seen {
+                let new_text = text::unseen_suffix(&self.confirmed_seen, &result.confirmed).to_string();
+                if !new_text.is_empty() {
+                    self.queue_confirmed(&new_text);
+                }
+                self.confirmed_seen = result.confirmed;
+            }
+            self.pending_transcription = result.pending;
+            self.transcript_segments.extend(result.segments);
+        }
+        if session_ended {
+            self.transcription_rx = None;
+            self.transcription_session = None;
+            self.finish_transcription_output();
+        }
 
         // Opacity check - commented out for compatibility
         // frame.set_window_opacity(self.opacity);
@@ -348,28 +1239,44 @@ impl eframe::App for AutoTyperApp {
             // Audio status indicators
             ui.horizontal(|ui| {
                 if self.is_dictating {
-                    let status_text = if self.mic_muted { "🟡 MUTED" } else { "🔴 LISTENING" };
-                    let status_color = if self.mic_muted { 
+                    let mic_paused = self.mic_paused();
+                    let status_text = if mic_paused { "🟡 PAUSED" } else { "🔴 LISTENING" };
+                    let status_color = if mic_paused { 
                         egui::Color32::from_rgb(255, 200, 0) 
                     } else { 
                         egui::Color32::from_rgb(255, 100, 100) 
                     };
                     ui.colored_label(status_color, status_text);
-                    if !self.mic_muted {
+                    if !mic_paused {
                         ui.spinner();
                     }
                     
-                    // Audio level indicator
+                    // Audio level indicator, on a dB scale so quiet speech still moves it
                     if let Some(capture) = &self.audio_capture {
                         let level = capture.get_audio_level();
+                        let peak = capture.get_peak_level();
+                        let color = if peak >= 0.99 {
+                            egui::Color32::from_rgb(230, 80, 80) // Clipping
+                        } else if level > self.settings.vad_threshold.max(audio::SPEECH_RMS_THRESHOLD) {
+                            egui::Color32::from_rgb(90, 200, 90) // Loud enough to count as speech
+                        } else {
+                            egui::Color32::GRAY
+                        };
                         ui.add_space(10.0);
                         ui.label("🔊");
-                        ui.add(egui::ProgressBar::new(level).desired_width(100.0).show_percentage());
+                        ui.add(egui::ProgressBar::new(meter_fraction(level))
+                            .desired_width(100.0)
+                            .fill(color)
+                            .text(format!("{:.0} dB", to_dbfs(level))))
+                            .on_hover_text(format!("RMS {:.0} dBFS, peak {:.0} dBFS", to_dbfs(level), to_dbfs(peak)));
                     }
                 }
             });
             
             ui.label(egui::RichText::new(&self.model_status).small().weak());
+            if self.model_load_rx.is_some() {
+                ui.add(egui::ProgressBar::new(self.model_progress).desired_width(200.0).show_percentage());
+            }
             ui.add_space(10.0);
 
             // Header Controls
@@ -394,9 +1301,27 @@ impl eframe::App for AutoTyperApp {
                 ui.separator();
 
                 // Load Model
-                if ui.add_enabled(self.transcriber.is_none() && self.model_load_rx.is_none(), egui::Button::new("📥 Load Model")).clicked() {
+                if ui.add_enabled(self.needs_model_load() && self.model_load_rx.is_none(), egui::Button::new("📥 Load Model")).clicked() {
                     self.load_model();
                 }
+                
+                // Quick switch between recently used models
+                if self.settings.recent_models.len() > 1 {
+                    let recent: Vec<WhisperModel> = self.settings.recent_models.iter()
+                        .map(|m| WhisperModel::from_settings_str(m))
+                        .collect();
+                    ui.add_enabled_ui(self.model_load_rx.is_none() && !self.is_dictating, |ui| {
+                        egui::ComboBox::from_id_salt("recent_cb")
+                            .selected_text(format!("⚡ {}", self.selected_model.display_name()))
+                            .show_ui(ui, |ui| {
+                                for model in recent {
+                                    if ui.selectable_label(model == self.selected_model, model.display_name()).clicked() {
+                                        self.switch_model(model);
+                                    }
+                                }
+                            });
+                    });
+                }
 
                 ui.separator();
                 
@@ -411,19 +1336,64 @@ impl eframe::App for AutoTyperApp {
                     }
                 }
                 
-                // Mute/Unmute toggle button (only visible when dictating)
-                // When muted, transcription results are still processed but not displayed
-                if self.is_dictating {
-                    let mute_text = if self.mic_muted { "🎙 Unmute" } else { "🔇 Mute" };
-                    if ui.button(mute_text).clicked() {
-                        self.mic_muted = !self.mic_muted;
+                // Pause/resume the mic (only while dictating from it)
+                if self.is_dictating && self.audio_capture.is_some() {
+                    let pause_text = if self.mic_paused() { "▶ Resume Mic" } else { "⏸ Pause Mic" };
+                    if ui.button(pause_text).clicked() {
+                        self.toggle_mic_pause();
                     }
                 }
                 
+                // Live typing has already typed the text elsewhere, so it can't be taken back
+                if ui.add_enabled(!self.inserted_chunks.is_empty() && self.live_type_tx.is_none(), egui::Button::new("↶ Undo last"))
+                    .on_hover_text("Remove the most recently inserted transcription from the box")
+                    .on_disabled_hover_text("Nothing to undo (or live typing has already typed it)")
+                    .clicked()
+                {
+                    self.undo_last_insertion();
+                }
+                
                 if ui.add_enabled(!self.is_dictating && self.transcriber.is_some(), egui::Button::new("📂 Upload Audio")).clicked() {
                     self.upload_audio_file();
                 }
+                
+                if ui.add_enabled(self.demo_rx.is_none() && !self.is_dictating && self.transcriber.is_some(), egui::Button::new("🎧 Try Demo"))
+                    .on_hover_text("Transcribe a short sample clip to check the model works")
+                    .clicked()
+                {
+                    self.run_demo();
+                }
+                
+                if ui.add_enabled(self.export_rx.is_none() && !self.is_dictating && self.transcriber.is_some(), egui::Button::new("📝 Export Annotated"))
+                    .on_hover_text("Transcribe a file and save it with low-confidence segments marked ?like this?")
+                    .clicked()
+                {
+                    self.export_annotated();
+                }
+                
+                if ui.add_enabled(!self.is_dictating && !self.transcript_segments.is_empty(), egui::Button::new("💾 Export"))
+                    .on_hover_text("Save the last transcription as text, or as SRT/VTT subtitles when timestamps are on")
+                    .clicked()
+                {
+                    self.export_transcript();
+                }
+                
+                let batch_idle = self.batch_rx.is_none();
+                if ui.add_enabled(batch_idle && !self.is_dictating && self.transcriber.is_some(), egui::Button::new("📁 Transcribe Folder")).clicked() {
+                    self.transcribe_folder();
+                }
             });
+            
+            // Folder transcription progress
+            if self.batch_rx.is_some() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::ProgressBar::new(self.batch_progress).desired_width(200.0).show_percentage());
+                    ui.label(egui::RichText::new(&self.batch_status).small());
+                    if ui.button("⏹ Cancel").clicked() {
+                        self.batch_stop.store(true, Ordering::Relaxed);
+                    }
+                });
+            }
 
             ui.add_space(10.0);
 
@@ -446,128 +1416,713 @@ impl eframe::App for AutoTyperApp {
                             configure_styles(ctx);
                         }
                         
+                        if ui.checkbox(&mut self.settings.always_on_top, "Always on top").changed() {
+                            self.settings.save();
+                            let level = if self.settings.always_on_top { egui::WindowLevel::AlwaysOnTop } else { egui::WindowLevel::Normal };
+                            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+                        }
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Opacity:");
+                            if ui.add(egui::Slider::new(&mut self.settings.opacity, 0.3..=1.0).show_value(true)).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Idle refresh:");
+                            if ui.add(egui::Slider::new(&mut self.settings.idle_repaint_ms, 50..=2000).suffix(" ms")).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        ui.label(egui::RichText::new("How often the window updates while nothing is running. Higher saves CPU.").small().weak());
+                        
+                        ui.add_space(10.0);
+                        ui.separator();
+                        ui.add_space(5.0);
+                        
+                        // ===== 🤖 Model Section =====
+                        ui.heading("🤖 Whisper Model");
+                        ui.add_space(8.0);
+                        
+                        let current_model_name = self.selected_model.display_name();
+                        egui::ComboBox::from_label("Model Size")
+                            .selected_text(current_model_name)
+                            .show_ui(ui, |ui| {
+                                for model in WhisperModel::all() {
+                                    let is_selected = *model == self.selected_model;
+                                    if ui.selectable_label(is_selected, model.display_name()).clicked() {
+                                        self.selected_model = *model;
+                                        self.settings.model_size = model.to_settings_str().to_string();
+                                        self.settings.save();
+                                        // Keep the loaded model usable until the new one is loaded
+                                        if let Some(loaded) = self.loaded_model {
+                                            if loaded != *model {
+                                                self.model_status = format!("Model changed. Click 'Load Model' to apply ({} stays active until then).", loaded.display_name());
+                                            }
+                                        }
+                                    }
+                                }
+                            });
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Device:");
+                            let devices = [("auto", "Auto (GPU if available)"), ("cpu", "CPU"), ("cuda", "CUDA"), ("metal", "Metal")];
+                            let current = devices.iter().find(|(value, _)| *value == self.settings.device).map_or("Auto (GPU if available)", |(_, label)| *label);
+                            egui::ComboBox::from_id_salt("device_combo")
+                                .selected_text(current)
+                                .show_ui(ui, |ui| {
+                                    for (value, label) in devices {
+                                        if ui.selectable_value(&mut self.settings.device, value.to_string(), label).changed() {
+                                            self.settings.save();
+                                        }
+                                    }
+                                });
+                        });
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Precision:");
+                            if ui.radio_value(&mut self.settings.precision, "auto".to_string(), "Auto").changed() {
+                                self.settings.save();
+                            }
+                            if ui.radio_value(&mut self.settings.precision, "f16".to_string(), "F16 (GPU)").changed() {
+                                self.settings.save();
+                            }
+                            if ui.radio_value(&mut self.settings.precision, "f32".to_string(), "F32").changed() {
+                                self.settings.save();
+                            }
+                        });
+                        
+                        ui.label(egui::RichText::new("Change requires reloading the model.").small().weak());
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("HF Token:");
+                            if ui.add(egui::TextEdit::singleline(&mut self.settings.hf_token)
+                                .password(true)
+                                .hint_text("hf_... (or HF_TOKEN env var)")
+                                .desired_width(200.0))
+                                .changed()
+                            {
+                                self.settings.save();
+                            }
+                        });
+                        ui.label(egui::RichText::new("Only needed for gated or private repos. Stored in the settings file.").small().weak());
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Local model folder:");
+                            if ui.add(egui::TextEdit::singleline(&mut self.settings.model_dir)
+                                .hint_text("(download from Hugging Face)")
+                                .desired_width(200.0))
+                                .changed()
+                            {
+                                self.settings.save();
+                            }
+                            if ui.button("📁").on_hover_text("Choose folder").clicked() {
+                                if let Some(dir) = FileDialog::new().pick_folder() {
+                                    self.settings.model_dir = dir.display().to_string();
+                                    self.settings.save();
+                                }
+                            }
+                        });
+                        ui.label(egui::RichText::new("Offline mode: load config, tokenizer, weights and mel filters from this folder without any network access. Leave empty to download.").small().weak());
+                        
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(self.needs_model_load() && self.model_load_rx.is_none(), egui::Button::new("📥 Load Model")).clicked() {
+                                self.load_model();
+                            }
+                            if ui.add_enabled(self.verify_rx.is_none() && self.model_load_rx.is_none() && self.model_dir().is_none(), egui::Button::new("🩺 Verify Files"))
+                                .on_hover_text("Check the cached files of the selected model against Hugging Face")
+                                .clicked()
+                            {
+                                self.verify_model_cache();
+                            }
+                        });
+                        
+                        ui.add_space(10.0);
+                        ui.separator();
+                        ui.add_space(8.0);
+                        
+                        // ===== 🎤 Audio Section =====
+                        ui.heading("🎤 Audio Input");
+                        ui.add_space(8.0);
+                        
+                        let current_mic = self.selected_mic.as_deref().unwrap_or("Default (Auto)");
+                        egui::ComboBox::from_label("Microphone")
+                            .selected_text(current_mic)
+                            .show_ui(ui, |ui| {
+                                // Default option
+                                if ui.selectable_label(self.selected_mic.is_none(), "Default (Auto)").clicked() {
+                                    self.selected_mic = None;
+                                }
+                                
+                                for mic in &self.available_mics {
+                                    let is_selected = Some(mic) == self.selected_mic.as_ref();
+                                    if ui.selectable_label(is_selected, mic).clicked() {
+                                        self.selected_mic = Some(mic.clone());
+                                    }
+                                }
+                            });
+                        
+                        if ui.button("🔄 Refresh Devices").clicked() {
+                            self.available_mics = list_input_devices();
+                            if self.selected_mic.is_none() {
+                                self.selected_mic = get_default_input_device_name();
+                            }
+                        }
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Input channel:");
+                            let channel_label = |c: usize| match c {
+                                0 => "Mix all".to_string(),
+                                1 => "Channel 1 (left)".to_string(),
+                                2 => "Channel 2 (right)".to_string(),
+                                c => format!("Channel {}", c),
+                            };
+                            egui::ComboBox::from_id_salt("input_channel_cb")
+                                .selected_text(channel_label(self.settings.input_channel))
+                                .show_ui(ui, |ui| {
+                                    for c in 0..=8 {
+                                        if ui.selectable_value(&mut self.settings.input_channel, c, channel_label(c)).changed() {
+                                            self.settings.save();
+                                        }
+                                    }
+                                });
+                        });
+                        ui.label(egui::RichText::new("Also used for uploaded and batch files. Pick one channel when a headset puts the mic on only one side.").small().weak());
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Input gain:");
+                            if ui.add(egui::Slider::new(&mut self.settings.input_gain_db, -12.0..=24.0).step_by(1.0).suffix(" dB")).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        if ui.checkbox(&mut self.settings.auto_gain, "Automatic gain control").changed() {
+                            self.settings.save();
+                        }
+                        ui.label(egui::RichText::new("Boost a quiet mic so Whisper hears every word. AGC evens out the level on top of the gain; peaks are limited so loud input doesn't clip. The level meter shows the mic before gain. Applies the next time dictation starts.").small().weak());
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Resampler chunk:");
+                            egui::ComboBox::from_id_salt("resampler_chunk_cb")
+                                .selected_text(format!("{} frames", self.settings.resampler_chunk))
+                                .show_ui(ui, |ui| {
+                                    for frames in [256, 512, 1024, 2048, 4096] {
+                                        if ui.selectable_value(&mut self.settings.resampler_chunk, frames, format!("{} frames", frames)).changed() {
+                                            self.settings.save();
+                                        }
+                                    }
+                                });
+                        });
+                        ui.label(egui::RichText::new("Only used when the mic doesn't run at 16kHz. Applies on next start.").small().weak());
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Chunk length:");
+                            if ui.add(egui::Slider::new(&mut self.settings.chunk_ms, 100..=2000).step_by(50.0).suffix("ms")).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Context window:");
+                            if ui.add(egui::Slider::new(&mut self.settings.context_secs, 5..=30).suffix("s")).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Poll interval:");
+                            if ui.add(egui::Slider::new(&mut self.settings.poll_ms, 50..=1000).step_by(10.0).suffix("ms")).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        ui.label(egui::RichText::new("Shorter chunks and polling lower latency on a fast GPU; raise them if a slow CPU can't keep up. Applies on next start.").small().weak());
+                        
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{} hotkey:", self.settings.hotkeys.dictate));
+                            let hotkey_label = |m: &str| match m {
+                                "toggle" => "Toggle dictation",
+                                "push_to_talk" => "Push-to-talk (hold)",
+                                _ => "Off",
+                            };
+                            egui::ComboBox::from_id_salt("dictation_hotkey_cb")
+                                .selected_text(hotkey_label(&self.settings.dictation_hotkey))
+                                .show_ui(ui, |ui| {
+                                    for m in ["toggle", "push_to_talk", "off"] {
+                                        if ui.selectable_value(&mut self.settings.dictation_hotkey, m.to_string(), hotkey_label(m)).changed() {
+                                            self.settings.save();
+                                        }
+                                    }
+                                });
+                        });
+                        ui.label(egui::RichText::new("Works while another window is focused.").small().weak());
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Auto-stop after silence:");
+                            if ui.add(egui::Slider::new(&mut self.settings.silence_timeout_secs, 0..=300).suffix("s")).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        ui.label(egui::RichText::new("0 = keep listening until stopped.").small().weak());
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Speech threshold:");
+                            if ui.add(egui::Slider::new(&mut self.settings.vad_threshold, 0.0..=0.1).logarithmic(true).fixed_decimals(3)).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        ui.label(egui::RichText::new("Input quieter than this isn't transcribed, and a 1s pause finalizes the text. 0 = transcribe everything.").small().weak());
+                        
+                        if ui.checkbox(&mut self.settings.save_recordings, "Save dictation audio (16kHz WAV)").changed() {
+                            self.settings.save();
+                        }
+                        if let Some(dir) = AppSettings::recordings_dir() {
+                            ui.label(egui::RichText::new(format!("Saved to {}", dir.display())).small().weak());
+                        }
+                        
+                        ui.add_space(10.0);
+                        ui.separator();
+                        ui.add_space(8.0);
+
+                        // ===== 🎙 Transcription Section =====
+                        ui.heading("🎙 Transcription");
+                        ui.add_space(5.0);
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Profile:");
+                            let selected = self.settings.matching_profile().unwrap_or("Custom").to_string();
+                            let names: Vec<String> = self.settings.decoding_profiles.iter().map(|p| p.name.clone()).collect();
+                            egui::ComboBox::from_id_salt("profile_cb")
+                                .selected_text(selected.as_str())
+                                .show_ui(ui, |ui| {
+                                    for name in names {
+                                        if ui.selectable_label(selected == name, &name).clicked()
+                                            && self.settings.apply_profile(&name)
+                                        {
+                                            self.profile_name_input = name;
+                                            self.settings.save();
+                                        }
+                                    }
+                                });
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut self.profile_name_input)
+                                .hint_text("Profile name")
+                                .desired_width(160.0));
+                            let name = self.profile_name_input.trim().to_string();
+                            if ui.add_enabled(!name.is_empty(), egui::Button::new("💾 Save")).clicked() {
+                                self.settings.save_profile(&name);
+                                self.settings.save();
+                            }
+                            let exists = self.settings.decoding_profiles.iter().any(|p| p.name == name);
+                            if ui.add_enabled(exists, egui::Button::new("🗑 Delete")).clicked() {
+                                self.settings.delete_profile(&name);
+                                self.settings.save();
+                            }
+                        });
+                        ui.add_space(5.0);
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Task:");
+                            if ui.radio_value(&mut self.settings.task, "transcribe".to_string(), "Transcribe").changed() {
+                                self.settings.save();
+                            }
+                            if ui.radio_value(&mut self.settings.task, "translate".to_string(), "Translate to English").changed() {
+                                self.settings.save();
+                            }
+                        });
+                        if self.translate_unavailable() {
+                            ui.label(egui::RichText::new("The loaded model is English-only and can't translate; pick a multilingual model.").small().color(egui::Color32::YELLOW));
+                        }
+                        
+                        // English-only models have no language tokens
+                        if !WhisperModel::from_settings_str(&self.settings.model_size).is_english_only() {
+                            ui.horizontal(|ui| {
+                                ui.label("Language:");
+                                let selected = match self.settings.language.as_deref() {
+                                    None => "Auto-detect".to_string(),
+                                    Some(code) => LANGUAGES
+                                        .iter()
+                                        .find(|(c, _)| *c == code)
+                                        .map(|(_, name)| name.to_string())
+                                        .unwrap_or_else(|| code.to_string()),
+                                };
+                                let mut changed = false;
+                                egui::ComboBox::from_id_salt("language_combo")
+                                    .selected_text(selected)
+                                    .show_ui(ui, |ui| {
+                                        changed |= ui.selectable_value(&mut self.settings.language, None, "Auto-detect").changed();
+                                        for (code, name) in LANGUAGES {
+                                            changed |= ui.selectable_value(&mut self.settings.language, Some(code.to_string()), *name).changed();
+                                        }
+                                    });
+                                if changed {
+                                    self.settings.save();
+                                }
+                            });
+                            if self.language_invalid() {
+                                ui.label(egui::RichText::new("The loaded model doesn't know this language; auto-detecting instead.").small().color(egui::Color32::YELLOW));
+                            }
+                        }
+                        
+                        if ui.checkbox(&mut self.settings.timestamps, "Show Timestamps").changed() {
+                            self.settings.save();
+                        }
+                        if ui.checkbox(&mut self.settings.word_timestamps, "Word-level timestamps").changed() {
+                            self.settings.save();
+                        }
+                        ui.label(egui::RichText::new("Times each word for SRT/VTT export by aligning the text with the audio. Costs one extra decoder pass per 30s of audio.").small().weak());
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Insert dictation:");
+                            if ui.radio_value(&mut self.settings.dictation_insert, "anchor".to_string(), "Where it left off").changed() {
+                                self.settings.save();
+                            }
+                            if ui.radio_value(&mut self.settings.dictation_insert, "end".to_string(), "At end of text").changed() {
+                                self.settings.save();
+                            }
+                        });
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Warmup before first result:");
+                            if ui.add(egui::Slider::new(&mut self.settings.warmup_secs, 1.0..=5.0).step_by(0.5).suffix("s")).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Normalize level:");
+                            if ui.radio_value(&mut self.settings.normalize_input, "off".to_string(), "Off").changed() {
+                                self.settings.save();
+                            }
+                            if ui.radio_value(&mut self.settings.normalize_input, "peak".to_string(), "Peak").changed() {
+                                self.settings.save();
+                            }
+                            if ui.radio_value(&mut self.settings.normalize_input, "rms".to_string(), "RMS").changed() {
+                                self.settings.save();
+                            }
+                        });
+                        
+                        if ui.checkbox(&mut self.settings.greedy_only, "Fast greedy decoding (no temperature fallback)")
+                            .on_hover_text("One deterministic pass per segment. Faster, but can't recover from repetition on noisy audio.")
+                            .changed()
+                        {
+                            self.settings.save();
+                        }
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Beam size:");
+                            if ui.add(egui::Slider::new(&mut self.settings.beam_size, 1..=8)).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        ui.label(egui::RichText::new("1 = greedy. Beam search keeps several guesses and can recover words on noisy audio, but every beam runs its own decoder pass: on CPU, 2 beams took about 2x as long as greedy, 5 beams about 4.7x and 8 beams about 8x. Used at temperature 0 only.").small().weak());
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Repetition limit:");
+                            if ui.add(egui::Slider::new(&mut self.settings.max_repeats, 0..=10)).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        ui.label(egui::RichText::new("A word or phrase repeated back to back more often than this (\"you you you you...\") is treated as a hallucination: decoded again hotter, then cut to one copy. 0 = off.").small().weak());
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Log-prob threshold:");
+                            if ui.add(egui::Slider::new(&mut self.settings.logprob_threshold, -3.0..=0.0).step_by(0.1)).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Compression ratio threshold:");
+                            if ui.add(egui::Slider::new(&mut self.settings.compression_ratio_threshold, 1.5..=4.0).step_by(0.1)).changed() {
+                                self.settings.save();
+                            }
+                        });
                         ui.horizontal(|ui| {
-                            ui.label("Opacity:");
-                            if ui.add(egui::Slider::new(&mut self.settings.opacity, 0.3..=1.0).show_value(true)).changed() {
+                            ui.label("No-speech threshold:");
+                            if ui.add(egui::Slider::new(&mut self.settings.no_speech_threshold, 0.0..=1.0).step_by(0.05)).changed() {
                                 self.settings.save();
                             }
                         });
+                        ui.label(egui::RichText::new("A decode less confident than the log-prob threshold, or more repetitive than the compression ratio, is retried at a higher temperature. A segment above the no-speech threshold that is also below the log-prob threshold is skipped as silence. Defaults: -1.0, 2.4, 0.6.").small().weak());
                         
-                        ui.add_space(10.0);
-                        ui.separator();
-                        ui.add_space(5.0);
+                        if ui.checkbox(&mut self.settings.clear_before_upload, "Clear the box before transcribing an uploaded file").changed() {
+                            self.settings.save();
+                        }
                         
-                        // ===== 🤖 Model Section =====
-                        ui.heading("🤖 Whisper Model");
-                        ui.add_space(8.0);
+                        if ui.checkbox(&mut self.settings.tidy_transcript, "Tidy spacing and capitalize sentences")
+                            .on_hover_text("Collapses double spaces, removes spaces before punctuation and capitalizes the first word of each sentence before text is inserted.")
+                            .changed()
+                        {
+                            self.settings.save();
+                        }
                         
-                        let current_model_name = self.selected_model.display_name();
-                        egui::ComboBox::from_label("Model Size")
-                            .selected_text(current_model_name)
-                            .show_ui(ui, |ui| {
-                                for model in WhisperModel::all() {
-                                    let is_selected = *model == self.selected_model;
-                                    if ui.selectable_label(is_selected, model.display_name()).clicked() {
-                                        self.selected_model = *model;
-                                        self.settings.model_size = model.to_settings_str().to_string();
-                                        self.settings.save();
-                                        // Clear loaded model if selection changed
-                                        if self.transcriber.is_some() {
-                                            self.transcriber = None;
-                                            self.model_status = "Model changed. Click 'Load Model' to apply.".to_string();
-                                        }
-                                    }
+                        if ui.checkbox(&mut self.settings.live_typing, "Type dictation live into the target window")
+                            .on_hover_text("Confirmed text goes to the box and is typed at the same time. Green = typed, yellow = waiting to be typed.")
+                            .changed()
+                        {
+                            self.settings.save();
+                        }
+                        
+                        ui.add_enabled_ui(!self.settings.greedy_only, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Start temperature:");
+                                if ui.add(egui::Slider::new(&mut self.settings.start_temperature, 0.0..=1.0).step_by(0.2)).changed() {
+                                    self.settings.save();
                                 }
                             });
+                        });
                         
-                        ui.label(egui::RichText::new("Change requires reloading the model.").small().weak());
-                        
-                        ui.add_space(5.0);
-                        if ui.button("📥 Load Model").clicked() {
-                            self.load_model();
+                        if ui.checkbox(&mut self.settings.punctuation_priming, "Punctuation priming")
+                            .on_hover_text("Prompts the model with a punctuated sentence so output comes out punctuated and capitalized.")
+                            .changed()
+                        {
+                            self.settings.save();
                         }
                         
-                        ui.add_space(10.0);
-                        ui.separator();
-                        ui.add_space(8.0);
+                        ui.label("Vocabulary prompt:");
+                        if ui.add(egui::TextEdit::multiline(&mut self.settings.initial_prompt)
+                            .hint_text("e.g. Kubernetes, PostgreSQL, Dr. Nguyen")
+                            .desired_rows(2)
+                            .desired_width(f32::INFINITY))
+                            .changed()
+                        {
+                            self.settings.save();
+                        }
+                        ui.label(egui::RichText::new("Names and jargon the model should expect. Long prompts keep only their last ~220 tokens.").small().weak());
                         
-                        // ===== 🎤 Audio Section =====
-                        ui.heading("🎤 Audio Input");
-                        ui.add_space(8.0);
+                        if ui.checkbox(&mut self.settings.retry_empty, "Retry hotter when speech gives no text (live)")
+                            .on_hover_text("Re-decodes from a higher temperature if the mic clearly had speech but nothing was recognized.")
+                            .changed()
+                        {
+                            self.settings.save();
+                        }
                         
-                        let current_mic = self.selected_mic.as_deref().unwrap_or("Default (Auto)");
-                        egui::ComboBox::from_label("Microphone")
-                            .selected_text(current_mic)
-                            .show_ui(ui, |ui| {
-                                // Default option
-                                if ui.selectable_label(self.selected_mic.is_none(), "Default (Auto)").clicked() {
-                                    self.selected_mic = None;
-                                }
-                                
-                                for mic in &self.available_mics {
-                                    let is_selected = Some(mic) == self.selected_mic.as_ref();
-                                    if ui.selectable_label(is_selected, mic).clicked() {
-                                        self.selected_mic = Some(mic.clone());
-                                    }
+                        if ui.checkbox(&mut self.settings.commit_on_punctuation, "Insert whole sentences only").changed() {
+                            self.settings.save();
+                        }
+                        ui.add_enabled_ui(self.settings.commit_on_punctuation, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Release anyway after:");
+                                if ui.add(egui::Slider::new(&mut self.settings.commit_max_chars, 20..=1000).suffix(" chars")).changed() {
+                                    self.settings.save();
                                 }
                             });
+                        });
                         
-                        if ui.button("🔄 Refresh Devices").clicked() {
-                            self.available_mics = list_input_devices();
-                            if self.selected_mic.is_none() {
-                                self.selected_mic = get_default_input_device_name();
+                        ui.horizontal(|ui| {
+                            ui.label("Flag in exports below:");
+                            if ui.add(egui::Slider::new(&mut self.settings.low_confidence, 0.0..=1.0).custom_formatter(|v, _| format!("{:.0}%", v * 100.0))).changed() {
+                                self.settings.save();
                             }
+                        });
+                        
+                        if ui.checkbox(&mut self.settings.verbose, "Verbose Logging (Debug)").changed() {
+                            self.settings.save();
                         }
                         
                         ui.add_space(10.0);
                         ui.separator();
-                        ui.add_space(8.0);
-
-                        // ===== 🎙 Transcription Section =====
-                        ui.heading("🎙 Transcription");
+                        ui.add_space(5.0);
+                        
+                        // ===== ⌨ Typing Section =====
+                        ui.heading("⌨ Typing");
                         ui.add_space(5.0);
                         
                         ui.horizontal(|ui| {
-                            ui.label("Task:");
-                            if ui.radio_value(&mut self.settings.task, "transcribe".to_string(), "Transcribe").changed() {
+                            ui.label("Default CPM:");
+                            if ui.add(egui::Slider::new(&mut self.settings.typing_speed_cpm, 300..=5000)).changed() {
                                 self.settings.save();
                             }
-                            if ui.radio_value(&mut self.settings.task, "translate".to_string(), "Translate to English").changed() {
+                        });
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Countdown:");
+                            if ui.add(egui::Slider::new(&mut self.settings.countdown_secs, 0..=30).suffix(" s")).changed() {
                                 self.settings.save();
                             }
                         });
+                        ui.label(egui::RichText::new("Time to click into the target window after START. 0 starts at once.").small().weak());
                         
-                        if ui.checkbox(&mut self.settings.timestamps, "Show Timestamps").changed() {
-                            self.settings.save();
-                        }
+                        ui.horizontal(|ui| {
+                            ui.label("Newlines:");
+                            if ui.radio_value(&mut self.settings.newline_mode, "lf".to_string(), "LF").changed() {
+                                self.settings.save();
+                            }
+                            if ui.radio_value(&mut self.settings.newline_mode, "crlf".to_string(), "CRLF").changed() {
+                                self.settings.save();
+                            }
+                            if ui.radio_value(&mut self.settings.newline_mode, "keep".to_string(), "Keep as-is").changed() {
+                                self.settings.save();
+                            }
+                        });
                         
-                        if ui.checkbox(&mut self.settings.verbose, "Verbose Logging (Debug)").changed() {
+                        if ui.checkbox(&mut self.settings.ascii_punctuation, "Replace smart quotes/dashes with ASCII").changed() {
                             self.settings.save();
                         }
                         
+                        ui.horizontal(|ui| {
+                            ui.label("Super-Human typos:");
+                            let mut percent = self.settings.typo_rate * 100.0;
+                            if ui.add(egui::Slider::new(&mut percent, 0.0..=20.0).step_by(0.5).suffix("%")).changed() {
+                                self.settings.typo_rate = percent / 100.0;
+                                self.settings.save();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Keyboard layout:");
+                            let current = KeyboardLayout::from_settings_str(&self.settings.keyboard_layout);
+                            egui::ComboBox::from_id_salt("layout_combo")
+                                .selected_text(current.display_name())
+                                .show_ui(ui, |ui| {
+                                    for layout in KeyboardLayout::all() {
+                                        if ui.selectable_label(current == *layout, layout.display_name()).clicked() {
+                                            self.settings.keyboard_layout = layout.to_settings_str().to_string();
+                                            self.settings.save();
+                                        }
+                                    }
+                                });
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Typo correction delay:");
+                            if ui.add(egui::Slider::new(&mut self.settings.correction_delay_ms, 0..=1000).step_by(10.0).suffix("ms")).changed() {
+                                self.settings.save();
+                            }
+                        });
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("Smart pause at:");
+                            if ui.radio_value(&mut self.settings.smart_pause_on, "space".to_string(), "Spaces").changed() {
+                                self.settings.save();
+                            }
+                            if ui.radio_value(&mut self.settings.smart_pause_on, "whitespace".to_string(), "Spaces, tabs & newlines").changed() {
+                                self.settings.save();
+                            }
+                            if ui.radio_value(&mut self.settings.smart_pause_on, "sentence".to_string(), "Sentence ends").changed() {
+                                self.settings.save();
+                            }
+                        });
+                        
+                        ui.horizontal(|ui| {
+                            ui.label("START while typing:");
+                            if ui.radio_value(&mut self.settings.overlapping_start, "reject".to_string(), "Ignore").changed() {
+                                self.settings.save();
+                            }
+                            if ui.radio_value(&mut self.settings.overlapping_start, "queue".to_string(), "Queue after current").changed() {
+                                self.settings.save();
+                            }
+                        });
+                        
                         ui.add_space(10.0);
                         ui.separator();
                         ui.add_space(5.0);
                         
-                        // ===== ⌨ Typing Section =====
-                        ui.heading("⌨ Typing");
+                        // ===== 🔑 Hotkeys Section =====
+                        ui.heading("🔑 Hotkeys");
                         ui.add_space(5.0);
                         
-                        ui.horizontal(|ui| {
-                            ui.label("Default CPM:");
-                            if ui.add(egui::Slider::new(&mut self.settings.typing_speed_cpm, 300..=5000)).changed() {
-                                self.settings.save();
+                        egui::Grid::new("hotkeys_grid").num_columns(3).show(ui, |ui| {
+                            for &action in HotkeyAction::all() {
+                                ui.label(action.label());
+                                if self.binding_action == Some(action) {
+                                    ui.label(egui::RichText::new("Press a key...").italics());
+                                    if ui.button("Cancel").clicked() {
+                                        self.capture_hotkey(None);
+                                    }
+                                } else {
+                                    ui.monospace(self.settings.hotkeys.get(action).to_string());
+                                    if ui.button("Change").clicked() {
+                                        self.capture_hotkey(Some(action));
+                                    }
+                                }
+                                ui.end_row();
                             }
                         });
+                        if !self.hotkey_error.is_empty() {
+                            ui.label(egui::RichText::new(&self.hotkey_error).small().color(egui::Color32::YELLOW));
+                        }
+                        if ui.button("Reset to defaults").clicked() {
+                            self.capture_hotkey(None);
+                            self.settings.hotkeys = Default::default();
+                            self.apply_hotkeys();
+                        }
+                        ui.label(egui::RichText::new("Hotkeys work in any window. Stop and Pause may share a key: one tap pauses, a quick second tap stops.").small().weak());
                     });
                 self.show_settings = is_open;
             }
 
             // Text Area
-            ui.add(egui::TextEdit::multiline(&mut self.text_to_type)
-                .hint_text("Paste text here...")
-                .desired_width(f32::INFINITY)
-                .desired_rows(10));
+            if self.live_sent > 0 {
+                // Color the dictated span: typed into the target vs still queued for typing
+                self.follow_box_edits();
+                let start = floor_char_boundary(&self.text_to_type, self.live_span_start);
+                let typed_end = floor_char_boundary(&self.text_to_type, start + self.live_typed.load(Ordering::Relaxed).min(self.live_sent));
+                let sent_end = floor_char_boundary(&self.text_to_type, start + self.live_sent);
+                let typed_color = egui::Color32::from_rgb(110, 200, 120);
+                let queued_color = egui::Color32::from_rgb(230, 190, 80);
+                
+                let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                    let font = egui::TextStyle::Body.resolve(ui.style());
+                    let normal = ui.visuals().text_color();
+                    let mut job = egui::text::LayoutJob::default();
+                    let bounds = [0, start, typed_end, sent_end, text.len()];
+                    let colors = [normal, typed_color, queued_color, normal];
+                    for (i, color) in colors.iter().enumerate() {
+                        let (a, b) = (bounds[i].min(text.len()), bounds[i + 1].min(text.len()));
+                        if a < b && text.is_char_boundary(a) && text.is_char_boundary(b) {
+                            job.append(&text[a..b], 0.0, egui::TextFormat::simple(font.clone(), *color));
+                        }
+                    }
+                    job.wrap.max_width = wrap_width;
+                    ui.fonts(|f| f.layout_job(job))
+                };
+                ui.add(egui::TextEdit::multiline(&mut self.text_to_type)
+                    .hint_text("Paste text here...")
+                    .desired_width(f32::INFINITY)
+                    .desired_rows(10)
+                    .layouter(&mut layouter));
+            } else {
+                ui.add(egui::TextEdit::multiline(&mut self.text_to_type)
+                    .hint_text("Paste text here...")
+                    .desired_width(f32::INFINITY)
+                    .desired_rows(10));
+            }
+            
+            // Not yet confirmed (or held for a sentence end); not typed anywhere
+            if self.is_dictating && (!self.held_confirmed.is_empty() || !self.pending_transcription.is_empty()) {
+                let pending = format!("{} {}", self.held_confirmed.trim(), self.pending_transcription).trim().to_string();
+                ui.label(egui::RichText::new(format!("… {}", pending)).italics().weak());
+            }
+            
+            // Finalized segments with their start times; clicking one replays the file from there
+            if !self.transcript_segments.is_empty() {
+                let mut seek_to = None;
+                let seekable = self.playback_path.is_some();
+                egui::CollapsingHeader::new(format!("🕒 Segments ({})", self.transcript_segments.len()))
+                    .id_salt("segments_list")
+                    .show(ui, |ui| {
+                        egui::ScrollArea::vertical()
+                            .max_height(160.0)
+                            .auto_shrink([false, true])
+                            .stick_to_bottom(true)
+                            .show(ui, |ui| {
+                                for (i, seg) in self.transcript_segments.iter().enumerate() {
+                                    ui.horizontal_wrapped(|ui| {
+                                        let time = egui::RichText::new(format_timecode(seg.start)).monospace();
+                                        if ui.add_enabled(seekable, egui::Button::new(time).small())
+                                            .on_hover_text("Play the file again from here")
+                                            .on_disabled_hover_text("Seeking works for uploaded files")
+                                            .clicked()
+                                        {
+                                            seek_to = Some(i);
+                                        }
+                                        ui.label(seg.dr.text.trim());
+                                    });
+                                }
+                            });
+                    });
+                if let Some(i) = seek_to {
+                    self.seek_playback(i);
+                }
+            }
             
             ui.add_space(10.0);
 
@@ -580,13 +2135,17 @@ impl eframe::App for AutoTyperApp {
                         1 => "Super-Human (Typo+Correct)",
                         2 => "Turbo (Instant)",
                         3 => "Block (Line-by-Line)",
+                        4 => "Paste (Clipboard only, no keystrokes)",
                         _ => "Unknown",
                     })
                     .show_ui(ui, |ui| {
                         ui.selectable_value(&mut self.mode, 0, "Natural");
                         ui.selectable_value(&mut self.mode, 1, "Super-Human (Typo+Correct)");
-                        ui.selectable_value(&mut self.mode, 2, "Turbo (Instant)");
+                        ui.selectable_value(&mut self.mode, 2, "Turbo (Instant)")
+                            .on_hover_text("Pastes the text, and types it as fast as possible if pasting fails.");
                         ui.selectable_value(&mut self.mode, 3, "Block (Line-by-Line)");
+                        ui.selectable_value(&mut self.mode, 4, "Paste (Clipboard only, no keystrokes)")
+                            .on_hover_text("One paste and nothing else; never falls back to typing. For games and remote desktops that drop typed characters. Your clipboard is restored afterwards.");
                     });
             });
 
@@ -602,7 +2161,42 @@ impl eframe::App for AutoTyperApp {
                 }
             });
             ui.label(egui::RichText::new(get_funny_label(cpm)).italics().weak());
-            ui.label(egui::RichText::new("Hotkeys: Alt+Shift+ (+/-) to change speed.").small().weak());
+            let keys = &self.settings.hotkeys;
+            ui.label(egui::RichText::new(format!(
+                "Hotkeys: {} / {} to change speed, {} to dictate.",
+                keys.speed_up, keys.speed_down, keys.dictate
+            )).small().weak());
+
+            // Target Window
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.label("Target:");
+                let any_window = "Focused window (click within countdown)";
+                let target_text = self.target_window.as_ref().map(|w| w.title.as_str()).unwrap_or(any_window);
+                egui::ComboBox::from_id_salt("target_cb")
+                    .selected_text(target_text)
+                    .width(350.0)
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(self.target_window.is_none(), any_window).clicked() {
+                            self.target_window = None;
+                        }
+                        for w in &self.target_windows {
+                            let is_selected = self.target_window.as_ref() == Some(w);
+                            if ui.selectable_label(is_selected, &w.title).clicked() {
+                                self.target_window = Some(w.clone());
+                            }
+                        }
+                    });
+                if ui.button("🔄").on_hover_text("Refresh window list").clicked() {
+                    self.target_windows = list_windows();
+                    // Drop the selection if that window has since closed
+                    if let Some(target) = &self.target_window {
+                        if !self.target_windows.iter().any(|w| w.id == target.id) {
+                            self.target_window = None;
+                        }
+                    }
+                }
+            });
 
             ui.add_space(15.0);
 
@@ -610,22 +2204,13 @@ impl eframe::App for AutoTyperApp {
             ui.horizontal(|ui| {
                 let is_running = self.running.load(Ordering::Relaxed);
                 
-                if ui.add_enabled(!is_running, egui::Button::new("▶ START (5s)").min_size(egui::vec2(100.0, 30.0))).clicked() {
-                     // Start Logic
-                     start_typing_thread(
-                         self.text_to_type.clone(),
-                         self.mode,
-                         self.speed_cpm.clone(),
-                         self.running.clone(),
-                         self.paused.clone(),
-                         self.pause_pending.clone(),
-                         self.stop_requested.clone(),
-                         self.status_rx.clone(), // This is wrong, need Sender. Creating channel in main.
-                     );
+                if ui.add_enabled(!is_running, egui::Button::new(format!("▶ START ({}s)", self.settings.countdown_secs)).min_size(egui::vec2(100.0, 30.0))).clicked() {
+                    self.request_start(self.text_to_type.clone());
                 }
 
                 let is_paused = self.paused.load(Ordering::Relaxed);
-                let pause_text = if is_paused { "▶ RESUME (ESC)" } else { "⏸ PAUSE (ESC)" };
+                let pause_key = &self.settings.hotkeys.pause;
+                let pause_text = if is_paused { format!("▶ RESUME ({})", pause_key) } else { format!("⏸ PAUSE ({})", pause_key) };
                 
                 if ui.add_enabled(is_running, egui::Button::new(pause_text).min_size(egui::vec2(100.0, 30.0))).clicked() {
                     if is_paused {
@@ -637,8 +2222,9 @@ impl eframe::App for AutoTyperApp {
                     }
                 }
 
-                if ui.add_enabled(is_running, egui::Button::new("⏹ STOP (2xESC)").min_size(egui::vec2(100.0, 30.0))).clicked() {
+                if ui.add_enabled(is_running, egui::Button::new(format!("⏹ STOP (2x{})", self.settings.hotkeys.stop)).min_size(egui::vec2(100.0, 30.0))).clicked() {
                     self.stop_requested.store(true, Ordering::Relaxed);
+                    self.queued_start = None;
                 }
             });
 
@@ -647,11 +2233,57 @@ impl eframe::App for AutoTyperApp {
             ui.add(egui::ProgressBar::new(self.progress));
         });
         
-        // Repaint for updates
-        ctx.request_repaint();
+        // Repaint continuously only while something is in progress
+        if self.has_pending_work() {
+            ctx.request_repaint();
+        } else {
+            ctx.request_repaint_after(Duration::from_millis(self.settings.idle_repaint_ms));
+        }
+    }
+}
+
+/// Fraction of the words in `expected` that also appear in `actual`,
+/// ignoring case and punctuation
+fn word_match_ratio(expected: &str, actual: &str) -> f32 {
+    let words = |s: &str| -> Vec<String> {
+        s.split_whitespace()
+            .map(|w| w.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase())
+            .filter(|w| !w.is_empty())
+            .collect()
+    };
+    let expected = words(expected);
+    let mut actual = words(actual);
+    if expected.is_empty() {
+        return 1.0;
+    }
+    let mut matched = 0;
+    for word in &expected {
+        if let Some(pos) = actual.iter().position(|w| w == word) {
+            actual.remove(pos);
+            matched += 1;
+        }
+    }
+    matched as f32 / expected.len() as f32
+}
+
+/// Format seconds as a short human-readable duration ("45s", "12 min", "1h 05m")
+fn format_duration(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{} min", (secs + 30) / 60)
+    } else {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
     }
 }
 
+/// Format a position in a recording as `mm:ss`, like the `[mm:ss]` transcript prefixes
+fn format_timecode(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
 fn get_funny_label(cpm: usize) -> String {
     if cpm < 500 { "🐢 Grandma".to_string() }
     else if cpm < 1200 { "👨‍💼 Average Joe".to_string() }
@@ -662,15 +2294,30 @@ fn get_funny_label(cpm: usize) -> String {
 }
 
 fn main() -> Result<(), eframe::Error> {
+    // `--transcribe` runs headless and never opens the window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if cli::wants_cli(&args) {
+        env_logger::init();
+        std::process::exit(cli::run(&args));
+    }
+    
     println!("DEBUG: Starting main...");
     env_logger::init();
 
+    // Restore the last window geometry, pulled back on screen if its monitor is gone
+    let saved = AppSettings::load();
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size(saved.window_size)
+        .with_transparent(true)
+        .with_decorations(true);
+    if let Some(pos) = saved.window_pos {
+        viewport = viewport.with_position(window::clamp_to_monitors(pos, saved.window_size, &window::monitor_rects()));
+    }
+    if saved.always_on_top {
+        viewport = viewport.with_always_on_top();
+    }
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([700.0, 600.0])
-            .with_transparent(true)
-            .with_always_on_top()
-            .with_decorations(true),
+        viewport,
         ..Default::default()
     };
 
@@ -680,76 +2327,90 @@ fn main() -> Result<(), eframe::Error> {
     let pause_pending = Arc::new(AtomicBool::new(false));
     let stop_requested = Arc::new(AtomicBool::new(false));
     let speed_cpm = Arc::new(AtomicUsize::new(1200));
+    let hotkeys = Arc::new(SharedHotkeys::new(saved.hotkeys.clone()));
 
     let (tx, rx) = unbounded();
     *GLOBAL_SENDER.lock() = Some(tx.clone());
 
-    // Global Input Listener (ESC & Hotkeys)
+    // Global Input Listener (configurable hotkeys)
     let r_run = running.clone();
     let r_stop = stop_requested.clone();
     let r_pause = paused.clone();
     let r_pend = pause_pending.clone();
     let r_speed = speed_cpm.clone();
     let r_tx = tx.clone();
+    let r_hotkeys = hotkeys.clone();
     
     thread::spawn(move || {
         println!("DEBUG: Typo thread spawned");
-        let mut last_esc = Instant::now();
-        // Variables for modifier state tracking
-        let mut alt_down = false;
-        let mut shift_down = false;
+        let mut last_stop_tap: Option<Instant> = None;
+        let mut mods = Modifiers::default();
+        let mut dictate_down = false; // Ignores key repeat while the dictation combo is held
 
         if let Err(error) = listen(move |event| {
             match event.event_type {
                 EventType::KeyPress(key) => {
-                    match key {
-                        RdevKey::Escape => {
-                            if r_run.load(Ordering::Relaxed) {
-                                if last_esc.elapsed() < Duration::from_millis(500) {
-                                    r_stop.store(true, Ordering::Relaxed);
-                                    let _ = r_tx.send(("STOPPED (Double ESC)".into(), 0.0, false));
-                                } else {
-                                    // Toggle Smart Pause
-                                    if r_pause.load(Ordering::Relaxed) {
-                                        r_pause.store(false, Ordering::Relaxed);
-                                        r_pend.store(false, Ordering::Relaxed);
-                                        let _ = r_tx.send(("RESUMED".into(), 0.0, false)); 
-                                    } else {
-                                        r_pend.store(true, Ordering::Relaxed);
-                                        let _ = r_tx.send(("Pausing at next space...".into(), 0.0, false)); 
-                                    }
-                                }
-                                last_esc = Instant::now();
-                            }
-                        }
-                        RdevKey::Alt | RdevKey::AltGr => alt_down = true,
-                        RdevKey::ShiftLeft | RdevKey::ShiftRight => shift_down = true,
-                        // Speed Up: + or =
-                        RdevKey::Equal | RdevKey::KpPlus => {
-                            if alt_down && shift_down {
-                                let old = r_speed.load(Ordering::Relaxed);
-                                r_speed.store(old + 100, Ordering::Relaxed);
-                                let _ = r_tx.send((format!("Speed UP: {}", old+100), 0.0, false));
-                            }
-                        },
-                        // Speed Down: - or _
-                        RdevKey::Minus | RdevKey::KpMinus => {
-                             if alt_down && shift_down {
-                                let old = r_speed.load(Ordering::Relaxed);
-                                if old > 100 {
-                                    r_speed.store(old - 100, Ordering::Relaxed);
-                                    let _ = r_tx.send((format!("Speed DOWN: {}", old-100), 0.0, false));
-                                }
+                    if mods.update(key, true) {
+                        return;
+                    }
+                    let combo = KeyCombo::pressed(key, mods);
+                    // While the settings wait for a new binding, the key is only recorded
+                    if r_hotkeys.capturing.swap(false, Ordering::Relaxed) {
+                        *r_hotkeys.captured.lock() = Some(combo);
+                        return;
+                    }
+                    let bindings = r_hotkeys.bindings.lock().clone();
+                    
+                    if r_run.load(Ordering::Relaxed) && (combo == bindings.stop || combo == bindings.pause) {
+                        let is_stop = combo == bindings.stop;
+                        if is_stop && last_stop_tap.is_some_and(|t| t.elapsed() < Duration::from_millis(500)) {
+                            r_stop.store(true, Ordering::Relaxed);
+                            let _ = r_tx.send((format!("STOPPED (Double {})", bindings.stop), 0.0, false));
+                        } else if combo == bindings.pause {
+                            // Toggle Smart Pause
+                            if r_pause.load(Ordering::Relaxed) {
+                                r_pause.store(false, Ordering::Relaxed);
+                                r_pend.store(false, Ordering::Relaxed);
+                                let _ = r_tx.send(("RESUMED".into(), 0.0, false)); 
+                            } else {
+                                r_pend.store(true, Ordering::Relaxed);
+                                let _ = r_tx.send(("Pausing at next space...".into(), 0.0, false)); 
                             }
                         }
-                        _ => {}
+                        if is_stop {
+                            last_stop_tap = Some(Instant::now());
+                        }
+                    }
+                    
+                    if combo == bindings.dictate && !dictate_down {
+                        dictate_down = true;
+                        r_hotkeys.dictate_held.store(true, Ordering::Relaxed);
+                        r_hotkeys.dictate_pressed.store(true, Ordering::Relaxed);
+                    } else if bindings.speed_up.matches(&combo) {
+                        let old = r_speed.load(Ordering::Relaxed);
+                        r_speed.store(old + 100, Ordering::Relaxed);
+                        let _ = r_tx.send((format!("Speed UP: {}", old+100), 0.0, false));
+                    } else if bindings.speed_down.matches(&combo) {
+                        let old = r_speed.load(Ordering::Relaxed);
+                        if old > 100 {
+                            r_speed.store(old - 100, Ordering::Relaxed);
+                            let _ = r_tx.send((format!("Speed DOWN: {}", old-100), 0.0, false));
+                        }
                     }
                 }
                 EventType::KeyRelease(key) => {
-                    match key {
-                        RdevKey::Alt | RdevKey::AltGr => alt_down = false,
-                        RdevKey::ShiftLeft | RdevKey::ShiftRight => shift_down = false,
-                        _ => {}
+                    mods.update(key, false);
+                    // Letting go of any key of the combo ends push-to-talk
+                    if dictate_down {
+                        let dictate = r_hotkeys.bindings.lock().dictate.clone();
+                        if hotkeys::key_name(key) == dictate.key
+                            || (dictate.ctrl && !mods.ctrl)
+                            || (dictate.alt && !mods.alt)
+                            || (dictate.shift && !mods.shift)
+                        {
+                            dictate_down = false;
+                            r_hotkeys.dictate_held.store(false, Ordering::Relaxed);
+                        }
                     }
                 }
                 _ => {}
@@ -764,7 +2425,7 @@ fn main() -> Result<(), eframe::Error> {
         options,
         Box::new(move |cc| {
             println!("DEBUG: Creating App Context");
-            Ok(Box::new(AutoTyperApp::new(cc, rx, running, paused, pause_pending, stop_requested, speed_cpm)))
+            Ok(Box::new(AutoTyperApp::new(cc, rx, running, paused, pause_pending, stop_requested, speed_cpm, hotkeys)))
         }),
     )
 }
@@ -774,6 +2435,9 @@ lazy_static::lazy_static! {
     static ref GLOBAL_SENDER: Mutex<Option<Sender<(String, f32, bool)>>> = Mutex::new(None);
 }
 
+/// Spawn the typing thread. Returns false (and spawns nothing) if a run is
+/// already in progress.
+#[allow(clippy::too_many_arguments)]
 fn start_typing_thread(
     text: String,
     mode: usize,
@@ -783,27 +2447,79 @@ fn start_typing_thread(
     pause_pending: Arc<AtomicBool>,
     stop_requested: Arc<AtomicBool>,
     _rx: Receiver<(String, f32, bool)>, 
-) {
+    target_window: Option<u64>,
+    settings: AppSettings,
+) -> bool {
     // Actually we don't need _rx here.
     // We need to access GLOBAL_SENDER to send updates back.
     
-    running.store(true, Ordering::Relaxed);
+    // Claim the run atomically so two triggers can never both spawn a thread
+    if running.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).is_err() {
+        return false;
+    }
+    
+    let text = text::normalize_output(&text, &settings.newline_mode, settings.ascii_punctuation);
+    
     paused.store(false, Ordering::Relaxed);
     pause_pending.store(false, Ordering::Relaxed);
     stop_requested.store(false, Ordering::Relaxed);
 
     thread::spawn(move || {
-        let mut enigo = Enigo::new(&enigo::Settings::default()).unwrap();
+        let mut enigo = match Enigo::new(&enigo::Settings::default()) {
+            Ok(enigo) => enigo,
+            Err(e) => {
+                send_status(format!("Keyboard init failed: {}", e), 0.0, false);
+                running.store(false, Ordering::Relaxed);
+                return;
+            }
+        };
         let total_chars = text.len();
         
-        // Countdown
-        for i in (1..=5).rev() {
-            if stop_requested.load(Ordering::Relaxed) { break; }
-             send_status(format!("Starting in {}s...", i), 0.0, false);
-            thread::sleep(Duration::from_secs(1));
+        // Countdown, checking for a stop every 100ms so it's honored right away
+        'countdown: for i in (1..=settings.countdown_secs).rev() {
+            send_status(format!("Starting in {}s...", i), 0.0, false);
+            for _ in 0..10 {
+                if stop_requested.load(Ordering::Relaxed) { break 'countdown; }
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+
+        // Bring the chosen target window to the front instead of relying on the user
+        let mut target_ok = true;
+        if let Some(id) = target_window {
+            if !stop_requested.load(Ordering::Relaxed) {
+                if let Err(e) = focus_target(id) {
+                    send_status(format!("Target window error: {}", e), 0.0, false);
+                    target_ok = false;
+                }
+            }
+        }
+
+        // Turbo and Paste paste the whole text at once; only Turbo falls back to typing
+        let paste_only = mode == 4;
+        let mut pasted = false;
+        let mut paste_failed = false;
+        if (mode == 2 || paste_only) && target_ok && !stop_requested.load(Ordering::Relaxed) {
+            while paused.load(Ordering::Relaxed) && !stop_requested.load(Ordering::Relaxed) {
+                send_status("PAUSED".into(), 0.0, true);
+                thread::sleep(Duration::from_millis(100));
+            }
+            if !stop_requested.load(Ordering::Relaxed) {
+                match paste_text(&mut enigo, &text) {
+                    Ok(()) => pasted = true,
+                    Err(e) if paste_only => {
+                        send_status(format!("Paste failed: {}", e), 0.0, false);
+                        paste_failed = true;
+                    }
+                    Err(e) => {
+                        log::warn!("Turbo paste failed, typing instead: {}", e);
+                        send_status(format!("Paste failed ({}), typing...", e), 0.0, false);
+                    }
+                }
+            }
         }
 
-        if !stop_requested.load(Ordering::Relaxed) {
+        if target_ok && !pasted && !paste_only && !stop_requested.load(Ordering::Relaxed) {
              send_status("Typing...".into(), 0.0, false);
              
              let mut i = 0;
@@ -813,12 +2529,21 @@ fn start_typing_thread(
                  if stop_requested.load(Ordering::Relaxed) { break; }
                  
                  // Handle Pausing
-                 check_smart_pause(&paused, &pause_pending, chars[i]);
+                 let prev = if i > 0 { Some(chars[i - 1]) } else { None };
+                 check_smart_pause(&paused, &pause_pending, prev, chars[i], &settings.smart_pause_on);
+                 let was_paused = paused.load(Ordering::Relaxed);
                  while paused.load(Ordering::Relaxed) {
                       if stop_requested.load(Ordering::Relaxed) { break; }
                       send_status("PAUSED".into(), (i as f32 / total_chars as f32), true);
                       thread::sleep(Duration::from_millis(100));
                  }
+                 // The user may have clicked elsewhere while paused
+                 if was_paused {
+                     if let Some(id) = target_window {
+                         let _ = window::focus_window(id);
+                         thread::sleep(Duration::from_millis(200));
+                     }
+                 }
                  
                  let ch = chars[i];
                  let cpm = speed_cpm.load(Ordering::Relaxed) as u64;
@@ -835,24 +2560,32 @@ fn start_typing_thread(
                              thread::sleep(Duration::from_millis(think));
                          } else {
                              // Typo Logic
-                             // let mut typed_correct = false; // Unused
-                             if rand::thread_rng().gen_bool(0.03) { // 3% typo
-                                if let Some(neighbor) = get_neighbor(ch) {
+                             let typo_rate = settings.typo_rate.clamp(0.0, 1.0) as f64;
+                             if typo_rate > 0.0 && rand::thread_rng().gen_bool(typo_rate) {
+                                if let Some(neighbor) = get_neighbor(ch, KeyboardLayout::from_settings_str(&settings.keyboard_layout)) {
                                     let _ = enigo.text(&neighbor.to_string());
-                                     thread::sleep(Duration::from_millis((base_delay_ms as f32 * 1.5) as u64)); // reaction
+                                    thread::sleep(Duration::from_millis(settings.correction_delay_ms)); // reaction
+                                    // Always take the wrong key back before honoring a stop or pause
                                     let _ = enigo.key(Key::Backspace, Direction::Click);
-                                    thread::sleep(Duration::from_millis(100));
+                                    thread::sleep(Duration::from_millis(base_delay_ms));
+                                    if stop_requested.load(Ordering::Relaxed) { break; }
+                                    while paused.load(Ordering::Relaxed) && !stop_requested.load(Ordering::Relaxed) {
+                                        send_status("PAUSED".into(), i as f32 / total_chars as f32, true);
+                                        thread::sleep(Duration::from_millis(100));
+                                    }
+                                    if stop_requested.load(Ordering::Relaxed) { break; }
                                 }
                              }
                              let _ = enigo.text(&ch.to_string());
                          }
                      },
-                     2 => { // Turbo
-                         // Actually this loop is inefficient for turbo, but implementing char by char for consistent structure
-                         // For real turbo we'd dump it all. Let's do char for now or refactor.
-                         // Simplification: Rust enigo sequence is fast.
-                         // .. implementing simple char type for now to save complexity
+                     2 => { // Turbo fallback when pasting failed: type without delays
                          let _ = enigo.text(&ch.to_string());
+                         i += 1;
+                         if i % 100 == 0 {
+                             send_status(format!("Typing... {}%", i * 100 / total_chars), i as f32 / total_chars as f32, false);
+                         }
+                         continue;
                      }
                      _ => { // Natural
                           let _ = enigo.text(&ch.to_string());
@@ -873,13 +2606,126 @@ fn start_typing_thread(
         }
 
         running.store(false, Ordering::Relaxed);
-        send_status("Done!".into(), 1.0, false);
+        if target_ok && !paste_failed {
+            send_status("Done!".into(), 1.0, false);
+        }
+    });
+    true
+}
+
+/// Spawn the thread that types dictated text as it is confirmed
+///
+/// Claims `running` like `start_typing_thread`, so it never types alongside a
+/// regular run; returns None if a run is already in progress. The thread
+/// exits (and releases `running`) once the returned sender is dropped.
+fn start_live_typing_thread(
+    running: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    stop_requested: Arc<AtomicBool>,
+    typed: Arc<AtomicUsize>,
+    target_window: Option<u64>,
+    settings: AppSettings,
+) -> Option<Sender<String>> {
+    if running.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).is_err() {
+        return None;
+    }
+    paused.store(false, Ordering::Relaxed);
+    stop_requested.store(false, Ordering::Relaxed);
+    
+    let (tx, rx) = unbounded::<String>();
+    thread::spawn(move || {
+        let mut enigo = match Enigo::new(&enigo::Settings::default()) {
+            Ok(enigo) => enigo,
+            Err(e) => {
+                send_status(format!("Keyboard init failed: {}", e), 0.0, false);
+                running.store(false, Ordering::Relaxed);
+                return;
+            }
+        };
+        
+        if let Some(id) = target_window {
+            if let Err(e) = focus_target(id) {
+                send_status(format!("Target window error: {}", e), 0.0, false);
+            }
+        }
+        
+        for chunk in rx.iter() {
+            while paused.load(Ordering::Relaxed) && !stop_requested.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(100));
+            }
+            if stop_requested.load(Ordering::Relaxed) {
+                break;
+            }
+            let out = text::normalize_output(&chunk, &settings.newline_mode, settings.ascii_punctuation);
+            if let Err(e) = enigo.text(&out) {
+                log::error!("Live typing failed: {}", e);
+            }
+            typed.fetch_add(chunk.len(), Ordering::Relaxed);
+        }
+        
+        running.store(false, Ordering::Relaxed);
     });
+    Some(tx)
+}
+
+/// Round a byte offset down to the nearest char boundary of `s`
+fn floor_char_boundary(s: &str, mut idx: usize) -> usize {
+    idx = idx.min(s.len());
+    while !s.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
+
+/// Paste `text` into the focused window through the clipboard, then put the
+/// previous clipboard text back
+fn paste_text(enigo: &mut Enigo, text: &str) -> anyhow::Result<()> {
+    let mut clipboard = Clipboard::new()?;
+    let previous = clipboard.get_text().ok();
+    let result = (|| -> anyhow::Result<()> {
+        clipboard.set_text(text)?;
+        thread::sleep(Duration::from_millis(50)); // Let the clipboard owner settle
+
+        let modifier = if cfg!(target_os = "macos") { Key::Meta } else { Key::Control };
+        enigo.key(modifier, Direction::Press)?;
+        let pasted = enigo.key(Key::Unicode('v'), Direction::Click);
+        let released = enigo.key(modifier, Direction::Release);
+        pasted?;
+        released?;
+
+        // The target reads the clipboard asynchronously; restore only after it had time to
+        thread::sleep(Duration::from_millis(300));
+        Ok(())
+    })();
+    // Put the user's clipboard back whether or not the paste went through
+    if let Some(previous) = previous {
+        let _ = clipboard.set_text(previous);
+    }
+    result
+}
+
+/// Focus the target window and wait until the window manager confirms it
+fn focus_target(id: u64) -> anyhow::Result<()> {
+    window::focus_window(id)?;
+    for _ in 0..10 {
+        thread::sleep(Duration::from_millis(100));
+        if window::foreground_window() == Some(id) {
+            return Ok(());
+        }
+    }
+    anyhow::bail!("window did not take focus (was it closed?)")
 }
 
-fn check_smart_pause(paused: &Arc<AtomicBool>, pending: &Arc<AtomicBool>, ch: char) {
+/// Turn a pending pause into a real one when `ch` is a boundary for `boundary_mode`
+/// ("space", "whitespace", or "sentence")
+fn check_smart_pause(paused: &Arc<AtomicBool>, pending: &Arc<AtomicBool>, prev: Option<char>, ch: char, boundary_mode: &str) {
     if pending.load(Ordering::Relaxed) {
-        if ch == ' ' || ch == '\n' || ch == '\t' {
+        let at_boundary = match boundary_mode {
+            "space" => ch == ' ',
+            "sentence" => (ch == ' ' || ch == '\n') && matches!(prev, Some('.' | '!' | '?')),
+            _ => ch == ' ' || ch == '\n' || ch == '\t',
+        };
+        if at_boundary {
             paused.store(true, Ordering::Relaxed);
             pending.store(false, Ordering::Relaxed);
         }
@@ -893,16 +2739,8 @@ fn send_status(msg: String, prog: f32, paused: bool) {
     }
 }
 
-fn get_neighbor(c: char) -> Option<char> {
-    let lower = c.to_lowercase().next()?;
-    for (k, v) in NEIGHBORS {
-        if k.starts_with(lower) {
-            let idx = rand::thread_rng().gen_range(0..v.len());
-            let n_char = v.chars().nth(idx)?;
-             return if c.is_uppercase() { Some(n_char.to_ascii_uppercase()) } else { Some(n_char) };
-        }
-    }
-    None
+fn get_neighbor(c: char, layout: KeyboardLayout) -> Option<char> {
+    layout.random_neighbor(c)
 }
 
 // Helpers for UI
@@ -3,8 +3,17 @@
 //! Handles fetching Whisper models using direct HTTP downloads.
 
 use anyhow::Result;
-use std::path::PathBuf;
-use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Download progress callback: file name, bytes so far, total bytes if known
+pub type DownloadProgress = Box<dyn Fn(&str, u64, Option<u64>) + Send + Sync>;
+
+/// Progress of one download: bytes so far and total bytes if known.
+/// Returning false cancels the download.
+type Report<'a> = &'a (dyn Fn(u64, Option<u64>) -> bool + Sync);
 
 /// Model variants available
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -15,6 +24,11 @@ pub enum WhisperModel {
     Tiny, // Multilingual
     Base,
     Small,
+    Medium,
+    Large,
+    LargeV3,
+    TinyEnQuantized, // 8-bit GGUF weights
+    TinyQuantized,
 }
 
 impl WhisperModel {
@@ -27,8 +41,37 @@ impl WhisperModel {
             WhisperModel::Tiny => "openai/whisper-tiny",
             WhisperModel::Base => "openai/whisper-base",
             WhisperModel::Small => "openai/whisper-small",
+            WhisperModel::Medium => "openai/whisper-medium",
+            WhisperModel::Large => "openai/whisper-large-v2",
+            WhisperModel::LargeV3 => "openai/whisper-large-v3",
+            WhisperModel::TinyEnQuantized | WhisperModel::TinyQuantized => "lmz/candle-whisper",
+        }
+    }
+
+    /// File names of the config, tokenizer and weights in the repo
+    pub fn files(&self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            WhisperModel::TinyEnQuantized => ("config-tiny-en.json", "tokenizer-tiny-en.json", "model-tiny-en-q80.gguf"),
+            WhisperModel::TinyQuantized => ("config-tiny.json", "tokenizer-tiny.json", "model-tiny-q80.gguf"),
+            _ => ("config.json", "tokenizer.json", "model.safetensors"),
+        }
+    }
+
+    /// Mel bins the model's input has (large-v3 uses 128, all others 80)
+    pub fn num_mel_bins(&self) -> usize {
+        match self {
+            WhisperModel::LargeV3 => 128,
+            _ => 80,
         }
     }
+
+    /// English-only models can't translate and have no language tokens
+    pub fn is_english_only(&self) -> bool {
+        matches!(
+            self,
+            WhisperModel::TinyEn | WhisperModel::BaseEn | WhisperModel::SmallEn | WhisperModel::TinyEnQuantized
+        )
+    }
     
     /// Get the specific revision (commit hash) to pin if needed, or "main"
     pub fn revision(&self) -> &'static str {
@@ -44,20 +87,35 @@ impl WhisperModel {
             WhisperModel::Tiny => "Tiny (39MB, Multilingual)",
             WhisperModel::Base => "Base (74MB, Multilingual)",
             WhisperModel::Small => "Small (244MB, Multilingual)",
+            WhisperModel::Medium => "Medium (769MB, Multilingual)",
+            WhisperModel::Large => "Large-v2 (1.5GB, Multilingual)",
+            WhisperModel::LargeV3 => "Large-v3 (1.5GB, Most Accurate)",
+            WhisperModel::TinyEnQuantized => "Tiny.en (Quantized, 40MB)",
+            WhisperModel::TinyQuantized => "Tiny (Quantized, 40MB, Multilingual)",
         }
     }
 
     /// Convert from settings string
     pub fn from_settings_str(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
+        Self::parse(s).unwrap_or(WhisperModel::BaseEn) // Default fallback
+    }
+
+    /// Parse a settings string or Whisper-style name like `base.en`
+    pub fn parse(s: &str) -> Option<Self> {
+        Some(match s.to_lowercase().as_str() {
             "tiny_en" | "tiny.en" => WhisperModel::TinyEn,
             "base_en" | "base.en" => WhisperModel::BaseEn,
             "small_en" | "small.en" => WhisperModel::SmallEn,
             "tiny" => WhisperModel::Tiny,
             "base" => WhisperModel::Base,
             "small" => WhisperModel::Small,
-            _ => WhisperModel::BaseEn, // Default fallback
-        }
+            "medium" => WhisperModel::Medium,
+            "large" | "large_v2" => WhisperModel::Large,
+            "large_v3" => WhisperModel::LargeV3,
+            "tiny_en_q80" => WhisperModel::TinyEnQuantized,
+            "tiny_q80" => WhisperModel::TinyQuantized,
+            _ => return None,
+        })
     }
 
     /// Convert to settings string
@@ -69,6 +127,11 @@ impl WhisperModel {
             WhisperModel::Tiny => "tiny",
             WhisperModel::Base => "base",
             WhisperModel::Small => "small",
+            WhisperModel::Medium => "medium",
+            WhisperModel::Large => "large",
+            WhisperModel::LargeV3 => "large_v3",
+            WhisperModel::TinyEnQuantized => "tiny_en_q80",
+            WhisperModel::TinyQuantized => "tiny_q80",
         }
     }
 
@@ -81,6 +144,11 @@ impl WhisperModel {
             WhisperModel::Tiny,
             WhisperModel::Base,
             WhisperModel::Small,
+            WhisperModel::Medium,
+            WhisperModel::Large,
+            WhisperModel::LargeV3,
+            WhisperModel::TinyEnQuantized,
+            WhisperModel::TinyQuantized,
         ]
     }
 }
@@ -91,6 +159,62 @@ impl Default for WhisperModel {
     }
 }
 
+/// Demo clip used by the "Try Demo" button (the same sample Candle's Whisper example uses)
+pub const DEMO_AUDIO_URL: &str = "https://huggingface.co/datasets/Narsil/candle-examples/resolve/main/samples_jfk.wav";
+
+/// What the demo clip says, for checking the transcription
+pub const DEMO_EXPECTED_TEXT: &str = "And so my fellow Americans, ask not what your country can do for you, ask what you can do for your country.";
+
+/// Hugging Face Hub download URL of a repo file
+fn hf_url(repo_id: &str, filename: &str) -> String {
+    format!("https://huggingface.co/{}/resolve/main/{}", repo_id, filename)
+}
+
+/// Name of the mel filter file for a model's number of mel bins
+fn mel_filters_file(mel_bins: usize) -> Result<&'static str> {
+    match mel_bins {
+        80 => Ok("melfilters.bytes"),
+        128 => Ok("melfilters128.bytes"),
+        _ => anyhow::bail!("Unsupported mel bins: {}", mel_bins),
+    }
+}
+
+/// File a download of `dest` is written to until it completes
+fn partial_path(dest: &Path) -> PathBuf {
+    dest.with_extension(match dest.extension() {
+        Some(ext) => format!("{}.partial", ext.to_string_lossy()),
+        None => "partial".to_string(),
+    })
+}
+
+/// Byte offset to resume a download from: the size of its partial file,
+/// which is deleted if it is larger than the real file can be
+fn resume_offset(part_path: &Path, expected_size: Option<u64>) -> Result<u64> {
+    let existing = std::fs::metadata(part_path).map(|m| m.len()).unwrap_or(0);
+    if expected_size.is_some_and(|size| existing > size) {
+        // Larger than the real file, can't be a valid prefix
+        std::fs::remove_file(part_path)?;
+        return Ok(0);
+    }
+    Ok(existing)
+}
+
+/// Move a finished partial file into place once it has the expected size.
+/// A short file stays behind to resume from; an oversized one is deleted.
+fn finish_download(part_path: &Path, dest: &Path, expected_size: Option<u64>) -> Result<()> {
+    let actual = std::fs::metadata(part_path)?.len();
+    if let Some(size) = expected_size {
+        if actual != size {
+            if actual > size {
+                std::fs::remove_file(part_path)?;
+            }
+            anyhow::bail!("incomplete, got {} of {} bytes", actual, size);
+        }
+    }
+    std::fs::rename(part_path, dest)?;
+    Ok(())
+}
+
 /// Paths to the essential files for a Whisper model
 #[derive(Debug, Clone)]
 pub struct ModelPaths {
@@ -99,14 +223,27 @@ pub struct ModelPaths {
     pub config: PathBuf,
 }
 
+/// Size and SHA-256 that Hugging Face publishes for a repo file
+#[derive(Debug, Default)]
+struct RemoteFile {
+    size: Option<u64>,
+    sha256: Option<String>,
+}
+
 /// Model manager for fetching models from HF Hub via direct HTTP
 pub struct ModelManager {
     cache_dir: PathBuf,
     client: reqwest::blocking::Client,
+    head_client: reqwest::blocking::Client, // Doesn't follow redirects, to read LFS headers
+    progress: Option<DownloadProgress>,
+    hf_token: Option<String>,
+    local_dir: Option<PathBuf>, // Offline: load model files from here, never download
 }
 
 impl ModelManager {
-    pub fn new() -> Result<Self> {
+    /// Create a manager. `hf_token` is sent to huggingface.co for gated or
+    /// private repos; when `None` the `HF_TOKEN` environment variable is used.
+    pub fn new(hf_token: Option<String>) -> Result<Self> {
         let project_dirs = directories::ProjectDirs::from("com", "auto-typer", "v6")
             .ok_or_else(|| anyhow::anyhow!("Could not determine cache directory"))?;
         let cache_dir = project_dirs.cache_dir().to_path_buf();
@@ -116,11 +253,174 @@ impl ModelManager {
             .timeout(std::time::Duration::from_secs(300))
             .build()?;
         
-        Ok(Self { cache_dir, client })
+        let head_client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        
+        let hf_token = hf_token
+            .or_else(|| std::env::var("HF_TOKEN").ok())
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty());
+        
+        Ok(Self { cache_dir, client, head_client, progress: None, hf_token, local_dir: None })
+    }
+
+    /// Work offline from `dir`, which must hold the model's config, tokenizer
+    /// and weights plus the mel filters. `None` downloads from the Hub.
+    pub fn set_local_dir(&mut self, dir: Option<PathBuf>) {
+        self.local_dir = dir;
+    }
+
+    /// Paths of the model's files in the offline directory, failing with a
+    /// list of every file that is missing
+    fn local_files(dir: &std::path::Path, model: WhisperModel) -> Result<ModelPaths> {
+        let (config_file, tokenizer_file, model_file) = model.files();
+        let mel_file = mel_filters_file(model.num_mel_bins())?;
+        let missing: Vec<&str> = [config_file, tokenizer_file, model_file, mel_file]
+            .into_iter()
+            .filter(|name| !dir.join(name).is_file())
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "Local model directory {} is missing {} (needed for {})",
+                dir.display(),
+                missing.join(", "),
+                model.display_name()
+            );
+        }
+        Ok(ModelPaths {
+            model: dir.join(model_file),
+            tokenizer: dir.join(tokenizer_file),
+            config: dir.join(config_file),
+        })
+    }
+
+    /// Attach the token to requests for the Hub (never to other hosts)
+    fn authorize(&self, request: reqwest::blocking::RequestBuilder, url: &str) -> reqwest::blocking::RequestBuilder {
+        match &self.hf_token {
+            Some(token) if url.starts_with("https://huggingface.co/") => request.bearer_auth(token),
+            _ => request,
+        }
+    }
+
+    /// Turn a failed HTTP status into an error that says what to do about it
+    fn http_error(&self, status: reqwest::StatusCode, url: &str) -> anyhow::Error {
+        match status.as_u16() {
+            401 if self.hf_token.is_some() => anyhow::anyhow!("Hugging Face rejected the access token (HTTP 401); check it in Settings: {}", url),
+            401 => anyhow::anyhow!("This repo needs a Hugging Face access token (HTTP 401); add one in Settings or set HF_TOKEN: {}", url),
+            403 => anyhow::anyhow!("Access denied (HTTP 403); accept the model's terms on huggingface.co with the token's account: {}", url),
+            404 => anyhow::anyhow!("Not found (HTTP 404); the repo may be private or the file missing: {}", url),
+            _ => anyhow::anyhow!("HTTP {}: {}", status, url),
+        }
+    }
+
+    /// Look up the published size and hash of a file on the Hub
+    ///
+    /// LFS files (the weights) answer with a redirect whose `x-linked-size` and
+    /// `x-linked-etag` headers hold the real size and SHA-256. Small files are
+    /// served directly and only their length is known.
+    fn remote_file_info(&self, url: &str) -> Result<RemoteFile> {
+        let response = self.authorize(self.head_client.head(url), url).send()?;
+        let status = response.status();
+        if !status.is_success() && !status.is_redirection() {
+            return Err(self.http_error(status, url));
+        }
+        let header = |name: &str| {
+            response.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.trim_matches('"').to_string())
+        };
+        let sha256 = header("x-linked-etag")
+            .filter(|etag| etag.len() == 64 && etag.chars().all(|c| c.is_ascii_hexdigit()))
+            .map(|etag| etag.to_lowercase());
+        let size = header("x-linked-size")
+            .or_else(|| header("content-length").filter(|_| status.is_success()))
+            .and_then(|v| v.parse().ok());
+        Ok(RemoteFile { size, sha256 })
+    }
+
+    /// Check a downloaded file against the published size and hash
+    fn check_file(path: &std::path::Path, remote: &RemoteFile) -> Result<()> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if let Some(size) = remote.size {
+            let actual = std::fs::metadata(path)?.len();
+            if actual != size {
+                anyhow::bail!("Downloaded file corrupt, please retry ({}: {} bytes, expected {})", name, actual, size);
+            }
+        }
+        if let Some(expected) = &remote.sha256 {
+            use sha2::{Digest, Sha256};
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+            let actual = format!("{:x}", hasher.finalize());
+            if &actual != expected {
+                anyhow::bail!("Downloaded file corrupt, please retry ({}: SHA-256 mismatch)", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-check the cached files of `model` against the Hub. A corrupt file is
+    /// deleted so the next load downloads it again. Returns the number of
+    /// files checked.
+    pub fn verify_cache(&self, model: WhisperModel) -> Result<usize> {
+        let repo_cache = self.cache_dir.join(model.repo_id().replace('/', "_"));
+        let (config_file, tokenizer_file, model_file) = model.files();
+        let mut checked = 0;
+        for filename in [config_file, tokenizer_file, model_file] {
+            let path = repo_cache.join(filename);
+            if !path.exists() {
+                continue;
+            }
+            let remote = self.remote_file_info(&hf_url(model.repo_id(), filename))?;
+            if let Err(e) = Self::check_file(&path, &remote) {
+                std::fs::remove_file(&path)?;
+                return Err(e);
+            }
+            checked += 1;
+        }
+        Ok(checked)
+    }
+
+    /// Report download progress to `callback` while fetching files
+    pub fn set_progress(&mut self, callback: impl Fn(&str, u64, Option<u64>) + Send + Sync + 'static) {
+        self.progress = Some(Box::new(callback));
+    }
+
+    /// Progress report for a single file, passed straight to the callback
+    fn report_file<'a>(&'a self, name: &'a str) -> impl Fn(u64, Option<u64>) -> bool + Sync + 'a {
+        move |done, total| {
+            if let Some(progress) = &self.progress {
+                progress(name, done, total);
+            }
+            true
+        }
+    }
+
+    /// Stream `reader` into `file` in chunks, reporting progress as it goes.
+    /// `start` is the number of bytes already in the file. Returns the bytes written.
+    fn copy_with_progress(&self, reader: &mut impl Read, file: &mut std::fs::File, start: u64, total: Option<u64>, report: Report) -> Result<u64> {
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut written = 0u64;
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buf[..n])?;
+            written += n as u64;
+            if !report(start + written, total) {
+                anyhow::bail!("Download cancelled");
+            }
+        }
+        file.flush()?;
+        Ok(written)
     }
 
     /// Download a file from HuggingFace Hub
-    fn download_hf_file(&self, repo_id: &str, filename: &str) -> Result<PathBuf> {
+    fn download_hf_file(&self, repo_id: &str, filename: &str, report: Report) -> Result<PathBuf> {
         // Create repo-specific cache directory
         let repo_cache = self.cache_dir.join(repo_id.replace('/', "_"));
         std::fs::create_dir_all(&repo_cache)?;
@@ -130,44 +430,92 @@ impl ModelManager {
         // Return cached file if exists
         if file_path.exists() {
             log::info!("Using cached: {:?}", file_path);
+            let size = std::fs::metadata(&file_path)?.len();
+            report(size, Some(size));
             return Ok(file_path);
         }
         
         // Build HuggingFace Hub URL
-        let url = format!(
-            "https://huggingface.co/{}/resolve/main/{}",
-            repo_id, filename
-        );
+        let url = hf_url(repo_id, filename);
         
         log::info!("Downloading: {}", url);
         
-        let response = self.client.get(&url).send()?;
+        let remote = self.remote_file_info(&url).unwrap_or_else(|e| {
+            log::warn!("Could not look up {} on the Hub, skipping verification: {}", filename, e);
+            RemoteFile::default()
+        });
         
-        if !response.status().is_success() {
-            anyhow::bail!("HTTP {}: {}", response.status(), url);
+        // Goes through a `.partial` file that an interrupted download resumes from
+        self.download_resumable(&url, &file_path, remote.size, report)?;
+        if let Err(e) = Self::check_file(&file_path, &remote) {
+            std::fs::remove_file(&file_path)?;
+            return Err(e);
         }
         
-        // Get content length for progress
-        let total_size = response.content_length().unwrap_or(0);
-        log::info!("File size: {} bytes", total_size);
-        
-        // Download to file
-        let bytes = response.bytes()?;
-        let mut file = std::fs::File::create(&file_path)?;
-        file.write_all(&bytes)?;
-        
         log::info!("Downloaded: {:?}", file_path);
         Ok(file_path)
     }
 
     /// Fetch the model files. This blocks while downloading.
+    ///
+    /// The files download in parallel and progress is reported for all of them
+    /// together, under the model's name. Each file is only moved into the cache
+    /// once complete, so if one fails the others are cancelled (keeping their
+    /// `.partial` files to resume from) and the cache holds only whole files.
     pub fn fetch_model(&self, model: WhisperModel) -> Result<ModelPaths> {
+        if let Some(dir) = &self.local_dir {
+            log::info!("=== Loading model from {:?} (offline) ===", dir);
+            return Self::local_files(dir, model);
+        }
         let repo_id = model.repo_id();
         log::info!("=== Fetching model: {} ===", repo_id);
         
-        let config = self.download_hf_file(repo_id, "config.json")?;
-        let tokenizer = self.download_hf_file(repo_id, "tokenizer.json")?;
-        let model_path = self.download_hf_file(repo_id, "model.safetensors")?;
+        let (config_file, tokenizer_file, model_file) = model.files();
+        let files = [config_file, tokenizer_file, model_file];
+        let progress = Mutex::new([(0u64, None::<u64>); 3]);
+        let failed = AtomicUsize::new(usize::MAX); // Index of the first file that failed
+        
+        let mut results: Vec<Result<PathBuf>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = files
+                .iter()
+                .enumerate()
+                .map(|(i, file)| {
+                    let (progress, failed) = (&progress, &failed);
+                    scope.spawn(move || {
+                        let report = |done: u64, total: Option<u64>| {
+                            let (done, total) = {
+                                let mut files = progress.lock().unwrap();
+                                files[i] = (done, total);
+                                (files.iter().map(|f| f.0).sum(), files.iter().map(|f| f.1).sum::<Option<u64>>())
+                            };
+                            if let Some(callback) = &self.progress {
+                                callback(model.display_name(), done, total);
+                            }
+                            failed.load(Ordering::Relaxed) == usize::MAX
+                        };
+                        let result = self.download_hf_file(repo_id, file, &report);
+                        if result.is_err() {
+                            let _ = failed.compare_exchange(usize::MAX, i, Ordering::Relaxed, Ordering::Relaxed);
+                        }
+                        result
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Download thread panicked"))))
+                .collect()
+        });
+        
+        // Report the failure that caused the others to be cancelled
+        let first_failed = failed.into_inner();
+        if first_failed != usize::MAX {
+            return Err(results.swap_remove(first_failed).unwrap_err());
+        }
+        let paths: Vec<PathBuf> = results.into_iter().collect::<Result<_>>()?;
+        let [config, tokenizer, model_path]: [PathBuf; 3] = paths
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Expected three model files"))?;
         
         log::info!("=== Model fetch complete ===");
 
@@ -178,19 +526,82 @@ impl ModelManager {
         })
     }
 
+    /// Download `url` to `dest` via a `.partial` file, resuming a previous partial
+    /// download when the server supports ranges. The final file is only moved
+    /// into place once its size matches `expected_size`, or else the length the
+    /// server announced.
+    fn download_resumable(&self, url: &str, dest: &std::path::Path, expected_size: Option<u64>, report: Report) -> Result<()> {
+        let part_path = partial_path(dest);
+        let existing = resume_offset(&part_path, expected_size)?;
+        
+        let mut request = self.authorize(self.client.get(url), url);
+        if existing > 0 {
+            log::info!("Resuming download at byte {}: {}", existing, url);
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+        }
+        let mut response = request.send()?;
+        
+        let resumed = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            // Partial file is already complete (or stale); start over
+            std::fs::remove_file(&part_path)?;
+            return self.download_resumable(url, dest, expected_size, report);
+        }
+        if !response.status().is_success() {
+            return Err(self.http_error(response.status(), url));
+        }
+        
+        let (mut file, start) = if resumed {
+            (std::fs::OpenOptions::new().append(true).open(&part_path)?, existing)
+        } else {
+            // Server ignored the range request, rewrite from the start
+            (std::fs::File::create(&part_path)?, 0)
+        };
+        let total = expected_size.or_else(|| response.content_length().map(|len| start + len));
+        self.copy_with_progress(&mut response, &mut file, start, total, report)?;
+        drop(file);
+        
+        finish_download(&part_path, dest, total)
+            .map_err(|e| anyhow::anyhow!("Download of {} failed: {}", url, e))
+    }
+
+    /// Fetch the demo speech clip, cached after the first download
+    pub fn fetch_demo_audio(&self) -> Result<PathBuf> {
+        let path = self.cache_dir.join("samples_jfk.wav");
+        if !path.exists() {
+            log::info!("Downloading demo audio from {}", DEMO_AUDIO_URL);
+            self.download_resumable(DEMO_AUDIO_URL, &path, None, &self.report_file("samples_jfk.wav"))?;
+        }
+        Ok(path)
+    }
+
     /// Fetch the Mel filter bytes from the Candle repository
     pub fn fetch_mel_filters(&self, mel_bins: usize) -> Result<PathBuf> {
-        let filename = match mel_bins {
-            80 => "melfilters.bytes",
-            128 => "melfilters128.bytes",
-            _ => anyhow::bail!("Unsupported mel bins: {}", mel_bins),
-        };
+        let filename = mel_filters_file(mel_bins)?;
+        // mel_bins x 201 FFT bins of little-endian f32
+        let expected_size = (mel_bins * 201 * 4) as u64;
+        
+        if let Some(dir) = &self.local_dir {
+            let path = dir.join(filename);
+            let size = std::fs::metadata(&path)
+                .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", path.display(), e))?
+                .len();
+            if size != expected_size {
+                anyhow::bail!("{} has {} bytes, expected {} for {} mel bins", path.display(), size, expected_size, mel_bins);
+            }
+            return Ok(path);
+        }
         
         let path = self.cache_dir.join(filename);
 
         if path.exists() {
-             log::info!("Mel filters found cached at {:?}", path);
-             return Ok(path);
+            let size = std::fs::metadata(&path)?.len();
+            if size == expected_size {
+                log::info!("Mel filters found cached at {:?}", path);
+                return Ok(path);
+            }
+            log::warn!("Cached mel filters {:?} have {} bytes, expected {}; re-downloading", path, size, expected_size);
+            std::fs::remove_file(&path)?;
         }
 
         let url = format!(
@@ -199,11 +610,65 @@ impl ModelManager {
         );
         
         log::info!("Downloading mel filters from {}", url);
-        let response = self.client.get(&url).send()?;
-        let bytes = response.bytes()?;
-        std::fs::write(&path, bytes)?;
+        self.download_resumable(&url, &path, Some(expected_size), &self.report_file(filename))?;
         
         Ok(path)
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_partial_is_kept_for_resuming() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.safetensors");
+        let part = partial_path(&dest);
+        assert_eq!(part, dir.path().join("model.safetensors.partial"));
+        std::fs::write(&part, [0u8; 600]).unwrap();
+
+        assert!(finish_download(&part, &dest, Some(1000)).is_err());
+        assert!(!dest.exists(), "a short download must not be cached");
+        assert_eq!(resume_offset(&part, Some(1000)).unwrap(), 600);
+    }
+
+    #[test]
+    fn complete_partial_is_moved_into_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("config.json");
+        let part = partial_path(&dest);
+        std::fs::write(&part, b"{}").unwrap();
+
+        finish_download(&part, &dest, Some(2)).unwrap();
+        assert!(!part.exists());
+        assert_eq!(std::fs::read(&dest).unwrap(), b"{}");
+    }
+
+    #[test]
+    fn oversized_partial_is_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("tokenizer");
+        let part = partial_path(&dest);
+        assert_eq!(part, dir.path().join("tokenizer.partial"));
+
+        std::fs::write(&part, [0u8; 20]).unwrap();
+        assert_eq!(resume_offset(&part, Some(10)).unwrap(), 0);
+        assert!(!part.exists());
+
+        std::fs::write(&part, [0u8; 20]).unwrap();
+        assert!(finish_download(&part, &dest, Some(10)).is_err());
+        assert!(!part.exists() && !dest.exists());
+    }
+
+    #[test]
+    fn unknown_size_is_accepted() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("samples.wav");
+        let part = partial_path(&dest);
+        assert_eq!(resume_offset(&part, None).unwrap(), 0);
+        std::fs::write(&part, [1u8; 5]).unwrap();
+        finish_download(&part, &dest, None).unwrap();
+        assert!(dest.exists());
+    }
+}
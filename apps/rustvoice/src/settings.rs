@@ -1,17 +1,118 @@
+use crate::decoder::{COMPRESSION_RATIO_THRESHOLD, LOGPROB_THRESHOLD, NO_SPEECH_THRESHOLD};
+use crate::hotkeys::Hotkeys;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
+/// How many models the quick-switch list remembers
+const MAX_RECENT_MODELS: usize = 4;
+
+/// A named bundle of decoding settings that can be applied in one step
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DecodingProfile {
+    pub name: String,
+    pub greedy_only: bool,
+    pub beam_size: usize,
+    pub max_repeats: usize,
+    pub no_speech_threshold: f64,
+    pub logprob_threshold: f64,
+    pub compression_ratio_threshold: f64,
+    pub start_temperature: f64,
+    pub retry_empty: bool,
+    pub warmup_secs: f32,
+    pub normalize_input: String,
+    pub punctuation_priming: bool,
+    pub context_secs: u32,
+    pub poll_ms: u64,
+}
+
+impl Default for DecodingProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            greedy_only: false,
+            beam_size: 1,
+            max_repeats: 4,
+            no_speech_threshold: NO_SPEECH_THRESHOLD,
+            logprob_threshold: LOGPROB_THRESHOLD,
+            compression_ratio_threshold: COMPRESSION_RATIO_THRESHOLD,
+            start_temperature: 0.0,
+            retry_empty: true,
+            warmup_secs: 2.0,
+            normalize_input: "off".to_string(),
+            punctuation_priming: false,
+            context_secs: 30,
+            poll_ms: 200,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)] // Fields missing from older settings files fall back to defaults
 pub struct AppSettings {
     pub typing_speed_cpm: usize,
     pub dark_mode: bool,
-    pub model_size: String, // "tiny_en", "base_en", "small_en", "tiny", "base", "small"
+    pub model_size: String, // "tiny_en", "base_en", "small_en", "tiny", "base", "small", "medium", "large", "large_v3", "tiny_en_q80", "tiny_q80"
+    pub recent_models: Vec<String>, // Most recently loaded first, same values as model_size
     pub opacity: f32,
+    pub always_on_top: bool,
+    pub window_pos: Option<[f32; 2]>, // Outer top-left corner, None = let the window manager place it
+    pub window_size: [f32; 2],        // Inner width and height
+    pub idle_repaint_ms: u64, // UI refresh interval when nothing is running
+    pub precision: String,  // "auto", "f16", or "f32"
+    pub device: String,     // "auto", "cpu", "cuda", or "metal"
+    pub hf_token: String,   // Hugging Face access token for gated/private repos ("" = HF_TOKEN env var)
+    pub model_dir: String,  // Offline: folder with the model files to load instead of downloading ("" = download)
     // Transcription options
     pub task: String,       // "transcribe" or "translate"
+    pub language: Option<String>, // ISO code for multilingual models, None = auto-detect
     pub timestamps: bool,
+    pub word_timestamps: bool, // Time each word for subtitle export (cross-attention alignment)
     pub verbose: bool,
+    // Decoding settings (bundled by `DecodingProfile`)
+    pub greedy_only: bool, // Skip the temperature fallback ladder for speed
+    pub beam_size: usize,  // Beam search width at temperature 0 (1 = greedy)
+    pub max_repeats: usize, // A phrase repeated back to back more often is a hallucination loop (0 = off)
+    pub no_speech_threshold: f64,         // Skip a segment as silence above this no-speech probability...
+    pub logprob_threshold: f64,           // ...when its average log probability is also below this; lower decodes are retried hotter
+    pub compression_ratio_threshold: f64, // Retry hotter when the text compresses better than this (repetitive)
+    pub start_temperature: f64, // Lowest temperature of the fallback ladder
+    pub retry_empty: bool, // Re-decode live audio hotter when speech produced no text
+    pub warmup_secs: f32,  // Audio to buffer before the first live transcription
+    pub normalize_input: String, // Level normalization per segment: "off", "peak", or "rms"
+    pub punctuation_priming: bool, // Prompt the model with punctuated text to bias its formatting
+    pub initial_prompt: String, // Terms/names to prime the decoder with ("" = no prompt)
+    pub decoding_profiles: Vec<DecodingProfile>,
+    pub active_profile: String, // Name of the last applied/saved profile
+    pub resampler_chunk: usize,    // Live resampler FFT size in input frames
+    pub chunk_ms: u32,             // Audio per chunk sent to the transcriber
+    pub context_secs: u32,         // Live decoding window, 5-30s
+    pub poll_ms: u64,              // Live transcriber check interval
+    pub input_channel: usize,      // Input and file channel: 0 = mix all, n = channel n (1 = left, 2 = right)
+    pub input_gain_db: f32,        // Live input gain, -12 to +24 dB
+    pub auto_gain: bool,           // Steer the live input toward a speech level (AGC)
+    pub dictation_hotkey: String,  // What `hotkeys.dictate` does: "toggle", "push_to_talk", or "off"
+    pub silence_timeout_secs: u32, // Auto-stop dictation after this much silence (0 = never)
+    pub save_recordings: bool,     // Keep each dictation's audio as a WAV in `recordings_dir()`
+    pub vad_threshold: f32,        // RMS level counted as speech for live decoding (0 = decode everything)
+    pub dictation_insert: String,  // "anchor" (keep place while the box is edited) or "end"
+    pub clear_before_upload: bool, // Empty the box before transcribing an uploaded file
+    pub tidy_transcript: bool,     // Fix spacing and capitalize sentence starts of inserted text
+    pub live_typing: bool,           // Also type confirmed dictation into the target window
+    pub commit_on_punctuation: bool, // Hold confirmed text until a sentence ends
+    pub commit_max_chars: usize,     // ...or until this much text is held
+    pub low_confidence: f32,  // Exports flag segments below this confidence (0.0 to 1.0)
+    // Typed output
+    pub countdown_secs: usize, // Delay before typing starts, to focus the target (0 = start at once)
+    pub newline_mode: String, // "lf", "crlf", or "keep"
+    pub ascii_punctuation: bool,
+    pub overlapping_start: String, // START while typing: "reject" or "queue"
+    pub smart_pause_on: String,    // Where a smart pause lands: "space", "whitespace", or "sentence"
+    pub typo_rate: f32,            // Super-Human: chance per character of hitting a neighbor key (0.0 to 0.2)
+    pub correction_delay_ms: u64,  // Super-Human: time before a typo is noticed and backspaced
+    pub keyboard_layout: String,   // Typo neighbor keys: "qwerty", "qwertz", "azerty", or "dvorak"
+    pub hotkeys: Hotkeys,          // Global key combos, matched by the input listener
 }
 
 impl Default for AppSettings {
@@ -20,10 +121,76 @@ impl Default for AppSettings {
             typing_speed_cpm: 1200,
             dark_mode: true,
             model_size: "base_en".to_string(),
+            recent_models: Vec::new(),
             opacity: 0.95,
+            always_on_top: true,
+            window_pos: None,
+            window_size: [700.0, 600.0],
+            idle_repaint_ms: 250,
+            precision: "auto".to_string(),
+            device: "auto".to_string(),
+            hf_token: String::new(),
+            model_dir: String::new(),
             task: "transcribe".to_string(),
+            language: None,
             timestamps: true,
+            word_timestamps: false,
             verbose: false,
+            greedy_only: false,
+            beam_size: 1,
+            max_repeats: 4,
+            no_speech_threshold: NO_SPEECH_THRESHOLD,
+            logprob_threshold: LOGPROB_THRESHOLD,
+            compression_ratio_threshold: COMPRESSION_RATIO_THRESHOLD,
+            start_temperature: 0.0,
+            retry_empty: true,
+            warmup_secs: 2.0,
+            normalize_input: "off".to_string(),
+            punctuation_priming: false,
+            initial_prompt: String::new(),
+            decoding_profiles: vec![
+                DecodingProfile {
+                    name: "Fast live dictation".to_string(),
+                    greedy_only: true,
+                    warmup_secs: 1.5,
+                    ..Default::default()
+                },
+                DecodingProfile {
+                    name: "Accurate file transcription".to_string(),
+                    retry_empty: false,
+                    warmup_secs: 3.0,
+                    normalize_input: "rms".to_string(),
+                    ..Default::default()
+                },
+            ],
+            active_profile: String::new(),
+            dictation_hotkey: "toggle".to_string(),
+            silence_timeout_secs: 0,
+            save_recordings: false,
+            vad_threshold: 0.01,
+            resampler_chunk: 1024,
+            chunk_ms: 500,
+            context_secs: 30,
+            poll_ms: 200,
+            input_channel: 0,
+            input_gain_db: 0.0,
+            auto_gain: false,
+            dictation_insert: "anchor".to_string(),
+            clear_before_upload: true,
+            tidy_transcript: true,
+            live_typing: false,
+            commit_on_punctuation: false,
+            commit_max_chars: 200,
+            low_confidence: 0.6,
+            countdown_secs: 5,
+            newline_mode: "lf".to_string(),
+            ascii_punctuation: false,
+            overlapping_start: "reject".to_string(),
+            smart_pause_on: "whitespace".to_string(),
+            typo_rate: 0.03,
+            correction_delay_ms: 150,
+            keyboard_layout: "qwerty".to_string(),
+            hotkeys: Hotkeys::default(),
         }
     }
 }
@@ -40,6 +207,81 @@ impl AppSettings {
         Self::default()
     }
 
+    /// The current decoding settings as a profile called `name`
+    fn profile_snapshot(&self, name: &str) -> DecodingProfile {
+        DecodingProfile {
+            name: name.to_string(),
+            greedy_only: self.greedy_only,
+            beam_size: self.beam_size,
+            max_repeats: self.max_repeats,
+            no_speech_threshold: self.no_speech_threshold,
+            logprob_threshold: self.logprob_threshold,
+            compression_ratio_threshold: self.compression_ratio_threshold,
+            start_temperature: self.start_temperature,
+            retry_empty: self.retry_empty,
+            warmup_secs: self.warmup_secs,
+            normalize_input: self.normalize_input.clone(),
+            punctuation_priming: self.punctuation_priming,
+            context_secs: self.context_secs,
+            poll_ms: self.poll_ms,
+        }
+    }
+
+    /// Name of the active profile, if the decoding settings still match it
+    pub fn matching_profile(&self) -> Option<&str> {
+        self.decoding_profiles
+            .iter()
+            .find(|p| p.name == self.active_profile)
+            .filter(|p| **p == self.profile_snapshot(&p.name))
+            .map(|p| p.name.as_str())
+    }
+
+    /// Snapshot the current decoding settings under `name`, replacing any profile with that name
+    pub fn save_profile(&mut self, name: &str) {
+        let profile = self.profile_snapshot(name);
+        match self.decoding_profiles.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = profile,
+            None => self.decoding_profiles.push(profile),
+        }
+        self.active_profile = name.to_string();
+    }
+
+    /// Copy a saved profile's decoding settings into the live settings
+    pub fn apply_profile(&mut self, name: &str) -> bool {
+        let Some(profile) = self.decoding_profiles.iter().find(|p| p.name == name).cloned() else {
+            return false;
+        };
+        self.greedy_only = profile.greedy_only;
+        self.beam_size = profile.beam_size;
+        self.max_repeats = profile.max_repeats;
+        self.no_speech_threshold = profile.no_speech_threshold;
+        self.logprob_threshold = profile.logprob_threshold;
+        self.compression_ratio_threshold = profile.compression_ratio_threshold;
+        self.start_temperature = profile.start_temperature;
+        self.retry_empty = profile.retry_empty;
+        self.warmup_secs = profile.warmup_secs;
+        self.normalize_input = profile.normalize_input;
+        self.punctuation_priming = profile.punctuation_priming;
+        self.context_secs = profile.context_secs;
+        self.poll_ms = profile.poll_ms;
+        self.active_profile = profile.name;
+        true
+    }
+
+    pub fn delete_profile(&mut self, name: &str) {
+        self.decoding_profiles.retain(|p| p.name != name);
+        if self.active_profile == name {
+            self.active_profile.clear();
+        }
+    }
+
+    /// Move a model to the front of the recently used list
+    pub fn remember_model(&mut self, model_size: &str) {
+        self.recent_models.retain(|m| m != model_size);
+        self.recent_models.insert(0, model_size.to_string());
+        self.recent_models.truncate(MAX_RECENT_MODELS);
+    }
+
     pub fn save(&self) {
         if let Some(path) = Self::get_config_path() {
             if let Some(parent) = path.parent() {
@@ -51,6 +293,12 @@ impl AppSettings {
         }
     }
 
+    /// Folder dictation recordings are saved to, next to the settings file
+    pub fn recordings_dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "AutoTyper", "AutoTyperV6")
+            .map(|proj_dirs| proj_dirs.config_dir().join("recordings"))
+    }
+
     fn get_config_path() -> Option<PathBuf> {
         directories::ProjectDirs::from("com", "AutoTyper", "AutoTyperV6")
             .map(|proj_dirs| proj_dirs.config_dir().join("settings.json"))
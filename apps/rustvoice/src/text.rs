@@ -0,0 +1,373 @@
+//! Text processing helpers for typed and transcribed output
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Normalize text before it is typed into another application
+///
+/// `newline_mode` is "lf", "crlf" or "keep" (see `AppSettings::newline_mode`).
+/// With `ascii_punctuation` typographic quotes, dashes and ellipses are
+/// replaced by their plain ASCII equivalents.
+pub fn normalize_output(text: &str, newline_mode: &str, ascii_punctuation: bool) -> String {
+    let mut out = match newline_mode {
+        "keep" => text.to_string(),
+        mode => {
+            // Collapse CRLF and lone CR to LF first so "\r\n" never becomes two line breaks
+            let unified = text.replace("\r\n", "\n").replace('\r', "\n");
+            if mode == "crlf" {
+                unified.replace('\n', "\r\n")
+            } else {
+                unified
+            }
+        }
+    };
+
+    if ascii_punctuation {
+        let mut ascii = String::with_capacity(out.len());
+        for c in out.chars() {
+            match c {
+                '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{2032}' => ascii.push('\''),
+                '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{2033}' | '«' | '»' => ascii.push('"'),
+                '\u{2012}' | '\u{2013}' => ascii.push('-'),
+                '\u{2014}' | '\u{2015}' => ascii.push_str("--"),
+                '\u{2026}' => ascii.push_str("..."),
+                '\u{00A0}' | '\u{2009}' | '\u{202F}' => ascii.push(' '),
+                '\u{200B}' | '\u{FEFF}' => {} // Zero-width characters
+                _ => ascii.push(c),
+            }
+        }
+        out = ascii;
+    }
+
+    out
+}
+
+/// Move a byte offset into `old` to the matching position in `new`
+///
+/// The edit is located by the common prefix and suffix of the two strings.
+/// Offsets before the edit stay put, offsets after it shift by the length
+/// change, and offsets inside the edited span land at the end of the
+/// replacement. An offset at the very end stays at the end. The result is
+/// always a char boundary of `new`.
+pub fn shift_offset(old: &str, new: &str, offset: usize) -> usize {
+    if offset >= old.len() {
+        return new.len();
+    }
+    let prefix: usize = old
+        .chars()
+        .zip(new.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let suffix: usize = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .scan(0, |total, len| {
+            *total += len;
+            (*total <= max_suffix).then_some(len)
+        })
+        .sum();
+
+    if offset <= prefix {
+        offset.min(new.len())
+    } else if offset >= old.len() - suffix {
+        (offset + new.len()).saturating_sub(old.len()).min(new.len())
+    } else {
+        new.len() - suffix
+    }
+}
+
+/// Get the part of `current` that follows the text already handled in `seen`
+///
+/// Normally `current` extends `seen` and the result is the new tail. When the
+/// transcriber revises text that was already inserted, the revision can't be
+/// taken back, so the same number of grapheme clusters is skipped instead.
+/// Both paths cut on grapheme boundaries and never split a character.
+pub fn unseen_suffix<'a>(seen: &str, current: &'a str) -> &'a str {
+    if let Some(rest) = current.strip_prefix(seen) {
+        return rest;
+    }
+    let skip = seen.graphemes(true).count();
+    match current.grapheme_indices(true).nth(skip) {
+        Some((idx, _)) => &current[idx..],
+        None => "",
+    }
+}
+
+/// Get the leading words of `current` that match `previous` word for word
+///
+/// Used to confirm live transcription: words two consecutive hypotheses
+/// agree on are unlikely to change again.
+pub fn common_word_prefix<'a>(previous: &str, current: &'a str) -> &'a str {
+    let mut end = 0;
+    for (a, b) in previous.split_whitespace().zip(current.split_whitespace()) {
+        if a != b {
+            break;
+        }
+        end = b.as_ptr() as usize - current.as_ptr() as usize + b.len();
+    }
+    &current[..end]
+}
+
+/// Find where to cut `text` so only complete sentences are released
+///
+/// Returns the byte offset just past the last sentence-ending punctuation mark
+/// that is followed by whitespace (or ends the text), including that whitespace.
+/// Full-width CJK marks end a sentence even without following whitespace. As
+/// in `tidy_transcript`, an ellipsis and the last period of an abbreviation
+/// like "e.g." don't end a sentence.
+pub fn sentence_boundary(text: &str) -> Option<usize> {
+    let mut boundary = None;
+    let mut last = None;
+    let mut word_has_dot = false;
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, n)| n);
+        let ellipsis = c == '.' && (last == Some('.') || next == Some('.'));
+        let abbreviation = c == '.' && word_has_dot;
+        word_has_dot = (word_has_dot || c == '.') && !c.is_whitespace();
+        last = Some(c);
+        if !ends_sentence(c) || ellipsis || abbreviation {
+            continue;
+        }
+        let mut end = idx + c.len_utf8();
+        match next {
+            None => boundary = Some(end),
+            Some(_) if matches!(c, '\u{3002}' | '\u{FF01}' | '\u{FF1F}') => boundary = Some(end),
+            Some(next) if next.is_whitespace() => {
+                while let Some(&(i, w)) = chars.peek() {
+                    if !w.is_whitespace() {
+                        break;
+                    }
+                    end = i + w.len_utf8();
+                    last = Some(w);
+                    chars.next();
+                }
+                word_has_dot = false;
+                boundary = Some(end);
+            }
+            _ => {}
+        }
+    }
+    boundary
+}
+
+/// Sentence-ending marks after which the next word is capitalized
+fn ends_sentence(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '\u{3002}' | '\u{FF01}' | '\u{FF1F}')
+}
+
+/// Tidy transcribed text before it goes into the box
+///
+/// Collapses runs of spaces, drops spaces before closing punctuation, adds a
+/// missing space after `,;!?` before a word, and capitalizes the first word
+/// of each sentence. `preceding` is the text the chunk is appended to, so
+/// spacing and sentence starts carry across chunks.
+///
+/// `language` is the ISO code of the transcript, if known. French keeps its
+/// space before `;:!?»`, Turkish and Azerbaijani capitalize `i` as `İ`.
+/// Scripts without case, such as CJK, only get the spacing fixes. An ellipsis,
+/// an abbreviation like "e.g." and a period inside a word ("3.5", "file.txt")
+/// don't start a new sentence.
+pub fn tidy_transcript(text: &str, preceding: &str, language: Option<&str>) -> String {
+    let french = language == Some("fr");
+    let dotted_i = matches!(language, Some("tr") | Some("az"));
+
+    let before = preceding.trim_end();
+    let mut capitalize = before.is_empty() || before.chars().last().is_some_and(ends_sentence) && !before.ends_with("..");
+    let mut after_terminator = false; // A sentence mark was just written; a space makes it final
+    let mut word_has_dot = false;     // The current word already had a period, e.g. "e.g."
+    let mut last = preceding.chars().last();
+    let mut pending_space = false;
+    let mut out = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if c == ' ' || c == '\t' {
+            pending_space = true;
+            if after_terminator {
+                capitalize = true;
+                after_terminator = false;
+            }
+            word_has_dot = false;
+            continue;
+        }
+
+        let closing = matches!(c, ',' | '.' | ';' | ':' | '!' | '?' | ')' | ']' | '}' | '%' | '\u{2026}' | '\u{3001}' | '\u{3002}' | '\u{FF0C}' | '\u{FF01}' | '\u{FF1F}')
+            && !(french && matches!(c, ';' | ':' | '!' | '?' | '»'));
+        let after_space = last.is_none_or(|l| l.is_whitespace());
+        if pending_space {
+            if !closing && !after_space {
+                out.push(' ');
+            }
+            pending_space = false;
+        } else if c.is_alphabetic() && last.is_some_and(|l| matches!(l, ',' | ';' | '!' | '?')) {
+            out.push(' ');
+            capitalize |= after_terminator;
+        }
+
+        if c.is_alphanumeric() {
+            if capitalize && c.is_alphabetic() {
+                if dotted_i && c == 'i' {
+                    out.push('\u{0130}');
+                } else {
+                    out.extend(c.to_uppercase());
+                }
+            } else {
+                out.push(c);
+            }
+            capitalize = false;
+            after_terminator = false;
+        } else {
+            out.push(c);
+            if ends_sentence(c) {
+                // Only the first mark of "..." counts, and not the end of an abbreviation
+                after_terminator = last != Some('.') && !(c == '.' && word_has_dot);
+                word_has_dot |= c == '.';
+            } else if !matches!(c, '"' | '\'' | ')' | ']' | '\u{00BB}' | '\u{201D}' | '\u{2019}') {
+                after_terminator = false;
+            }
+        }
+        last = Some(c);
+    }
+    if pending_space && !out.is_empty() {
+        out.push(' ');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchor_follows_edits_before_it() {
+        // "oh " typed at the start moves the anchor before "world" with it
+        let (old, new) = ("hello world", "oh hello world");
+        assert_eq!(&new[shift_offset(old, new, 6)..], "world");
+        // Deleting text before the anchor pulls it back
+        assert_eq!(&"world"[shift_offset(old, "world", 6)..], "world");
+    }
+
+    #[test]
+    fn anchor_ignores_edits_after_it() {
+        assert_eq!(shift_offset("hello world", "hello world!!", 6), 6);
+        assert_eq!(shift_offset("hello world", "hello there", 3), 3);
+        // An anchor at the end stays at the end
+        assert_eq!(shift_offset("hello world", "hello world!!", 11), 13);
+    }
+
+    #[test]
+    fn anchor_inside_an_edit_lands_after_the_replacement() {
+        let (old, new) = ("hello big world", "hello small world");
+        assert_eq!(&new[..shift_offset(old, new, 8)], "hello small");
+        let (old, new) = ("one big three", "one three");
+        assert_eq!(&new[shift_offset(old, new, 6)..], "three");
+    }
+
+    #[test]
+    fn anchor_stays_on_char_boundaries() {
+        // Anchor after the cup, the word before it rewritten
+        let (old, new) = ("café ☕ ok", "crème ☕ ok");
+        assert_eq!(&new[shift_offset(old, new, 9)..], " ok");
+        // é and è share their first byte; the edit must not split it
+        let (old, new) = ("aé b", "aè b");
+        for offset in 0..=old.len() {
+            if old.is_char_boundary(offset) {
+                assert!(new.is_char_boundary(shift_offset(old, new, offset)), "offset {offset}");
+            }
+        }
+        assert_eq!(shift_offset(old, new, 3), 3);
+        let (old, new) = ("日本語のテキスト", "日本語の長いテキスト");
+        assert_eq!(&new[shift_offset(old, new, 15)..], "キスト");
+    }
+
+    #[test]
+    fn new_text_extends_the_seen_hypothesis() {
+        assert_eq!(unseen_suffix("Hello", "Hello world"), " world");
+        assert_eq!(unseen_suffix("", "Hello"), "Hello");
+        assert_eq!(unseen_suffix("Hello world", "Hello"), "");
+    }
+
+    #[test]
+    fn revised_hypothesis_skips_what_was_inserted() {
+        // "I scream" was already inserted; only text past its length is new
+        assert_eq!(unseen_suffix("I scream", "Ice cream is good"), "m is good");
+        // A decomposed é counts as one character and is never split
+        assert_eq!(unseen_suffix("café", "cafe\u{301} noir"), " noir");
+        assert_eq!(unseen_suffix("日本", "日本語"), "語");
+    }
+
+    #[test]
+    fn agreement_stops_at_the_first_changed_word() {
+        let (previous, current) = ("the quick brown fox", "the quick brown box jumps");
+        assert_eq!(common_word_prefix(previous, current), "the quick brown");
+        assert_eq!(common_word_prefix("the quick", "the quick brown fox"), "the quick");
+        assert_eq!(common_word_prefix("a cat", "the cat"), "");
+    }
+
+    #[test]
+    fn sentence_boundary_releases_whole_sentences() {
+        assert_eq!(sentence_boundary("Hello there. How are"), Some(13));
+        assert_eq!(sentence_boundary("Is it?  Yes! And"), Some(13));
+        assert_eq!(sentence_boundary("Done."), Some(5));
+        assert_eq!(sentence_boundary("Hello there"), None);
+        assert_eq!(sentence_boundary("It costs 3.5 dollars"), None);
+        assert_eq!(sentence_boundary("你好。世界"), Some(9));
+    }
+
+    #[test]
+    fn sentence_boundary_skips_abbreviations_and_ellipses() {
+        assert_eq!(sentence_boundary("We use e.g. tests"), None);
+        assert_eq!(sentence_boundary("Well... maybe"), None);
+        assert_eq!(sentence_boundary("Well\u{2026} maybe"), None);
+        assert_eq!(sentence_boundary("See the U.S. today. Then"), Some(20));
+        assert_eq!(sentence_boundary("Wait... no. Go"), Some(12));
+    }
+
+    #[test]
+    fn tidies_spacing_and_sentence_starts() {
+        assert_eq!(
+            tidy_transcript("hello  world , how are you ?i am fine.thanks", "", None),
+            "Hello world, how are you? I am fine.thanks"
+        );
+    }
+
+    #[test]
+    fn keeps_abbreviations_and_ellipses() {
+        assert_eq!(
+            tidy_transcript(" this is e.g. a test... and more. next one", "Start.", None),
+            " This is e.g. a test... and more. Next one"
+        );
+    }
+
+    #[test]
+    fn continues_an_unfinished_sentence() {
+        assert_eq!(tidy_transcript(" and then", "We went home,", None), " and then");
+    }
+
+    #[test]
+    fn french_keeps_space_before_high_punctuation() {
+        assert_eq!(
+            tidy_transcript(" bonjour , ça va ? oui !", "", Some("fr")),
+            "Bonjour, ça va ? Oui !"
+        );
+    }
+
+    #[test]
+    fn turkish_capitalizes_dotted_i() {
+        assert_eq!(
+            tidy_transcript(" version 3.5 of file.txt is out.   istanbul is big", "", Some("tr")),
+            "Version 3.5 of file.txt is out. İstanbul is big"
+        );
+    }
+
+    #[test]
+    fn handles_cjk_quotes_and_inverted_marks() {
+        assert_eq!(tidy_transcript("你好 。世界", "", Some("zh")), "你好。世界");
+        assert_eq!(tidy_transcript(" \"quoted.\" next", "", None), "\"Quoted.\" Next");
+        assert_eq!(tidy_transcript("¿qué tal? bien", "", Some("es")), "¿Qué tal? Bien");
+    }
+}
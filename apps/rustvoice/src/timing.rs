@@ -0,0 +1,217 @@
+//! Word-level timestamps from the decoder's cross-attention
+//!
+//! Follows the reference implementation (`whisper/timing.py`): the attention
+//! of the alignment heads is normalized and smoothed, dynamic time warping
+//! finds the most likely monotonic path of the text tokens through the audio
+//! frames, and each word starts where the path reaches its first token.
+
+/// Audio frames per second the decoder attends to (the encoder halves the 100/s mel frames)
+pub const FRAMES_PER_SEC: f64 = 50.0;
+
+/// Width, in frames, of the median filter smoothing attention over time
+const MEDIAN_FILTER_WIDTH: usize = 7;
+
+/// Combine the attention scores of the alignment heads into one matrix
+///
+/// `scores` holds `heads` blocks of `tokens` x `frames` scores before
+/// softmax. Each head's rows are softmaxed over the frames, every frame is
+/// standardized across the tokens and the rows are median filtered over
+/// time, then the heads are averaged. Returns `tokens` x `frames` values.
+pub fn attention_matrix(scores: &[f32], heads: usize, tokens: usize, frames: usize) -> Vec<f32> {
+    let mut matrix = vec![0.0f32; tokens * frames];
+    if heads == 0 || tokens == 0 || frames == 0 {
+        return matrix;
+    }
+    for head in scores.chunks_exact(tokens * frames).take(heads) {
+        let mut weights = head.to_vec();
+        for row in weights.chunks_exact_mut(frames) {
+            softmax(row);
+        }
+        for frame in 0..frames {
+            let column = || (0..tokens).map(|t| weights[t * frames + frame]);
+            let mean = column().sum::<f32>() / tokens as f32;
+            let variance = column().map(|w| (w - mean).powi(2)).sum::<f32>() / (tokens.max(2) - 1) as f32;
+            let std = variance.sqrt().max(f32::EPSILON);
+            for t in 0..tokens {
+                let w = &mut weights[t * frames + frame];
+                *w = (*w - mean) / std;
+            }
+        }
+        for row in weights.chunks_exact_mut(frames) {
+            median_filter(row, MEDIAN_FILTER_WIDTH);
+        }
+        for (m, w) in matrix.iter_mut().zip(&weights) {
+            *m += w / heads as f32;
+        }
+    }
+    matrix
+}
+
+fn softmax(row: &mut [f32]) {
+    let max = row.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let mut sum = 0.0;
+    for x in row.iter_mut() {
+        *x = (*x - max).exp();
+        sum += *x;
+    }
+    for x in row.iter_mut() {
+        *x /= sum;
+    }
+}
+
+/// Replace each value by the median of the `width` values around it
+/// (odd `width`, edges mirrored)
+fn median_filter(row: &mut [f32], width: usize) {
+    let pad = width / 2;
+    let n = row.len();
+    if n <= pad {
+        return;
+    }
+    let reflect = |i: isize| -> usize {
+        if i < 0 {
+            i.unsigned_abs()
+        } else if i as usize >= n {
+            2 * (n - 1) - i as usize
+        } else {
+            i as usize
+        }
+    };
+    let source = row.to_vec();
+    let mut window = Vec::with_capacity(width);
+    for (i, out) in row.iter_mut().enumerate() {
+        let i = i as isize;
+        window.clear();
+        window.extend((i - pad as isize..=i + pad as isize).map(|j| source[reflect(j)]));
+        window.sort_by(f32::total_cmp);
+        *out = window[pad];
+    }
+}
+
+/// Cheapest path through a `rows` x `cols` cost matrix from the first cell
+/// to the last, as (row, column) pairs. Every step moves one row down, one
+/// column right, or both, so the path never goes backwards in either.
+pub fn dtw(cost: &[f32], rows: usize, cols: usize) -> Vec<(usize, usize)> {
+    let at = |i: usize, j: usize| i * (cols + 1) + j;
+    let mut total = vec![f32::INFINITY; (rows + 1) * (cols + 1)];
+    let mut trace = vec![0u8; (rows + 1) * (cols + 1)]; // 0 = diagonal, 1 = from above, 2 = from the left
+    total[0] = 0.0;
+    for j in 1..=cols {
+        for i in 1..=rows {
+            let diagonal = total[at(i - 1, j - 1)];
+            let above = total[at(i - 1, j)];
+            let left = total[at(i, j - 1)];
+            let (best, step) = if diagonal < above && diagonal < left {
+                (diagonal, 0)
+            } else if above < diagonal && above < left {
+                (above, 1)
+            } else {
+                (left, 2)
+            };
+            total[at(i, j)] = cost[(i - 1) * cols + j - 1] + best;
+            trace[at(i, j)] = step;
+        }
+    }
+
+    let (mut i, mut j) = (rows, cols);
+    let mut path = Vec::with_capacity(rows + cols);
+    while i > 0 && j > 0 {
+        path.push((i - 1, j - 1));
+        match trace[at(i, j)] {
+            0 => {
+                i -= 1;
+                j -= 1;
+            }
+            1 => i -= 1,
+            _ => j -= 1,
+        }
+    }
+    path.reverse();
+    path
+}
+
+/// Start and end times, in seconds from the start of the audio, of words
+/// made of consecutive text tokens
+///
+/// `matrix` (from `attention_matrix`) has one row per text token plus a last
+/// row for the end of the text, and `frames` columns. `word_lengths` are the
+/// words' token counts. A word starts when the alignment reaches its first
+/// token and ends when it reaches the next word.
+pub fn word_times(matrix: &[f32], frames: usize, word_lengths: &[usize]) -> Vec<(f64, f64)> {
+    if frames == 0 || matrix.len() < frames {
+        return vec![(0.0, 0.0); word_lengths.len()];
+    }
+    let rows = matrix.len() / frames;
+    let cost: Vec<f32> = matrix[..rows * frames].iter().map(|&w| -w).collect();
+    // Time at which the path first reaches each row
+    let mut jumps = Vec::with_capacity(rows);
+    for (row, frame) in dtw(&cost, rows, frames) {
+        if row == jumps.len() {
+            jumps.push(frame as f64 / FRAMES_PER_SEC);
+        }
+    }
+    let mut boundary = 0;
+    word_lengths
+        .iter()
+        .map(|&len| {
+            let start = jumps[boundary.min(rows - 1)];
+            boundary += len;
+            (start, jumps[boundary.min(rows - 1)])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Attention of `tokens` tokens, each focused on its own stretch of
+    /// `frames / tokens` frames, with some noise
+    fn staircase(tokens: usize, frames: usize) -> Vec<f32> {
+        let span = frames / tokens;
+        (0..tokens * frames)
+            .map(|i| {
+                let (t, f) = (i / frames, i % frames);
+                let noise = ((i * 7919) % 13) as f32 * 0.05;
+                if f / span == t { 4.0 + noise } else { noise }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dtw_path_is_monotonic_and_complete() {
+        let cost: Vec<f32> = staircase(5, 40).iter().map(|w| -w).collect();
+        let path = dtw(&cost, 5, 40);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(4, 39)));
+        for pair in path.windows(2) {
+            let ((r0, c0), (r1, c1)) = (pair[0], pair[1]);
+            assert!(r1 - r0 <= 1 && c1 - c0 <= 1 && (r1, c1) != (r0, c0));
+        }
+    }
+
+    #[test]
+    fn median_filter_removes_spikes() {
+        let mut row = [0.0, 0.0, 0.0, 9.0, 0.0, 0.0, 0.0, 0.0];
+        median_filter(&mut row, MEDIAN_FILTER_WIDTH);
+        assert!(row.iter().all(|&x| x == 0.0));
+    }
+
+    #[test]
+    fn word_times_follow_the_attention_and_increase() {
+        let (tokens, frames) = (6, 60);
+        let matrix = attention_matrix(&staircase(tokens, frames), 1, tokens, frames);
+        // Five text tokens in three words, plus the end-of-text row
+        let times = word_times(&matrix, frames, &[2, 1, 2]);
+        assert_eq!(times.len(), 3);
+        let mut previous_end = 0.0;
+        for &(start, end) in &times {
+            assert!(start >= previous_end && end >= start, "{times:?}");
+            previous_end = end;
+        }
+        // Tokens 0, 2 and 3 start at frames 0, 20 and 30
+        let expected = [0.0, 20.0 / FRAMES_PER_SEC, 30.0 / FRAMES_PER_SEC];
+        for (&(start, _), expected) in times.iter().zip(expected) {
+            assert!((start - expected).abs() <= 2.0 / FRAMES_PER_SEC, "{times:?}");
+        }
+    }
+}
@@ -1,11 +1,13 @@
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
 use candle_core as candle;
-use candle_core::{Device, Tensor};
+use candle_core::{DType, Device, Tensor};
 use candle_transformers::models::whisper::{self as m, Config, audio};
-use crate::decoder::{self, Decoder, Model, Task};
+use crate::decoder::{self, Decoder, Segment, Task, Thresholds};
+use crate::whisper_model::{Weights, Whisper};
 use crate::model::ModelPaths;
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use tokenizers::Tokenizer;
@@ -14,29 +16,135 @@ use byteorder::{ByteOrder, LittleEndian};
 pub struct TranscriptionResult {
     pub pending: String,
     pub confirmed: String,
+    /// Segments of a window finalized by this update, timed from the session start
+    pub segments: Vec<Segment>,
 }
 
+/// Handle to a live transcription thread. Stopping (or dropping) it makes the
+/// thread exit after its current decode, so a new session never overlaps it.
+pub struct TranscriptionSession {
+    stop: Arc<AtomicBool>,
+}
+
+impl TranscriptionSession {
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for TranscriptionSession {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Per-run decoding options, taken from the settings when a run starts
+#[derive(Debug, Clone, Default)]
+pub struct TranscribeOptions {
+    pub greedy_only: bool, // Single temperature-0 pass, no fallback
+    pub beam_size: usize,  // Beam search width for temperature-0 passes (0 or 1 = greedy)
+    pub max_repeats: usize, // Back-to-back repeats of a phrase treated as a hallucination loop (0 = off)
+    pub thresholds: Thresholds, // When a decode is retried hotter or skipped as silence
+    pub warmup_secs: f32,  // Audio to collect before the first live decode
+    pub normalize: String, // Level normalization before the mel: "off", "peak", or "rms"
+    pub start_temperature: f64, // Lowest fallback temperature to decode at
+    pub retry_empty: bool, // Live: re-decode hotter when speech gave no text
+    pub prompt: String,    // Text the decoder is conditioned on (style/vocabulary hints)
+    pub task: Task,        // Translate is ignored by English-only models
+    pub timestamps: bool,  // Decode with timestamp tokens and prefix text with [mm:ss]
+    pub word_timestamps: bool, // Attach per-word times to segments (one more decoder pass per segment)
+    pub language: Option<String>, // ISO code to pin the language, None = auto-detect
+    pub vad_threshold: f32, // Live: RMS level of speech; silent windows aren't decoded (0 = off)
+    pub context_secs: u32,  // Live: audio window decoded at once, 5-30s (0 = 30)
+    pub poll_ms: u64,       // Live: pause between checks for new audio, 20-2000ms (0 = 200)
+    pub start_secs: f64,    // Live: position of the first sample in the recording, for seeking
+}
+
+/// Well-punctuated prompt that biases Whisper toward punctuated, capitalized output
+pub const PUNCTUATION_PROMPT: &str = "Hello, welcome. This is a transcript with proper punctuation, capitalization, and full sentences.";
+
+/// Decoder prompt from the settings: the punctuation primer (if enabled)
+/// followed by the user's vocabulary prompt. When too long for the context
+/// the decoder keeps the end, so the vocabulary survives truncation.
+pub fn build_prompt(initial_prompt: &str, punctuation_priming: bool) -> String {
+    let primer = if punctuation_priming { PUNCTUATION_PROMPT } else { "" };
+    format!("{} {}", primer, initial_prompt.trim()).trim().to_string()
+}
+
+/// Languages offered for multilingual models, as (Whisper code, name)
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("en", "English"), ("zh", "Chinese"), ("de", "German"), ("es", "Spanish"),
+    ("ru", "Russian"), ("ko", "Korean"), ("fr", "French"), ("ja", "Japanese"),
+    ("pt", "Portuguese"), ("tr", "Turkish"), ("pl", "Polish"), ("ca", "Catalan"),
+    ("nl", "Dutch"), ("ar", "Arabic"), ("sv", "Swedish"), ("it", "Italian"),
+    ("id", "Indonesian"), ("hi", "Hindi"), ("fi", "Finnish"), ("vi", "Vietnamese"),
+    ("he", "Hebrew"), ("uk", "Ukrainian"), ("el", "Greek"), ("ms", "Malay"),
+    ("cs", "Czech"), ("ro", "Romanian"), ("da", "Danish"), ("hu", "Hungarian"),
+    ("ta", "Tamil"), ("no", "Norwegian"), ("th", "Thai"), ("ur", "Urdu"),
+    ("hr", "Croatian"), ("bg", "Bulgarian"), ("lt", "Lithuanian"), ("la", "Latin"),
+    ("cy", "Welsh"), ("sk", "Slovak"), ("te", "Telugu"), ("fa", "Persian"),
+    ("lv", "Latvian"), ("bn", "Bengali"), ("sr", "Serbian"), ("az", "Azerbaijani"),
+    ("sl", "Slovenian"), ("et", "Estonian"), ("mk", "Macedonian"), ("eu", "Basque"),
+    ("is", "Icelandic"), ("hy", "Armenian"), ("ne", "Nepali"), ("bs", "Bosnian"),
+    ("kk", "Kazakh"), ("sq", "Albanian"), ("sw", "Swahili"), ("gl", "Galician"),
+    ("mr", "Marathi"), ("af", "Afrikaans"), ("ka", "Georgian"), ("be", "Belarusian"),
+    ("tl", "Tagalog"),
+];
+
+/// Temperature a live retry starts from after an empty result on speech
+const RETRY_TEMPERATURE: f64 = 0.4;
+
+/// Live: trailing silence after which the window's text is final and the buffer restarts
+const VAD_FLUSH_SECS: f32 = 1.0;
+
+/// Live: audio kept from a silent buffer so the start of the next word isn't lost
+const VAD_KEEP_SECS: f32 = 0.5;
+
 pub struct WhisperTranscriber {
-    model: Model,
+    decoder: Mutex<Decoder>, // Reused for every decode; concurrent callers take turns
     tokenizer: Tokenizer,
     mel_filters: Vec<f32>,
     device: Device,
+    dtype: DType,
     config: Config,
 }
 
 impl WhisperTranscriber {
-    pub fn new(paths: ModelPaths, mel_filters_path: PathBuf) -> Result<Self> {
-        let device = Device::new_cuda(0).unwrap_or(Device::Cpu);
-        log::info!("Using device: {:?}", device);
+    /// Load a model. `precision` is "auto", "f16" or "f32"; `device` is
+    /// "auto", "cpu", "cuda" or "metal".
+    pub fn new(paths: ModelPaths, mel_filters_path: PathBuf, precision: &str, device: &str) -> Result<Self> {
+        let device = resolve_device(device)?;
+        let quantized = paths.model.extension().is_some_and(|ext| ext == "gguf");
+        // Quantized weights run with f32 activations whatever the precision setting
+        let dtype = if quantized { DType::F32 } else { resolve_dtype(precision, &device)? };
+        log::info!("Using device: {:?} ({:?})", device, dtype);
 
         let config: Config = serde_json::from_str(&std::fs::read_to_string(&paths.config)?)?;
         let tokenizer = Tokenizer::from_file(&paths.tokenizer).map_err(|e| anyhow!(e))?;
         
         // Load model weights
-        let vb = unsafe { 
-            candle_nn::VarBuilder::from_mmaped_safetensors(&[paths.model], m::DTYPE, &device)? 
+        let weights = if quantized {
+            Weights::Quantized(candle_transformers::quantized_var_builder::VarBuilder::from_gguf(&paths.model, &device)?)
+        } else {
+            Weights::Full(unsafe { 
+                candle_nn::VarBuilder::from_mmaped_safetensors(&[paths.model], dtype, &device)? 
+            })
         };
-        let model = Model::Normal(m::model::Whisper::load(&vb, config.clone())?);
+        let model = Whisper::load(&weights, config.clone())?;
+
+        // Built once: constructing a decoder rebuilds the suppress masks and token
+        // lookups, about 25 ms per decode with a tiny.en-sized vocabulary on CPU
+        let decoder = Decoder::new(
+            model,
+            tokenizer.clone(),
+            299792458, // Seed
+            &device,
+            None,
+            Some(Task::Transcribe),
+            true,
+            None,
+            false, // Verbose
+        )?;
 
         // Load mel filters
         let mel_bytes = std::fs::read(&mel_filters_path)?;
@@ -44,61 +152,222 @@ impl WhisperTranscriber {
         LittleEndian::read_f32_into(&mel_bytes, &mut mel_filters);
 
         Ok(Self {
-            model,
+            decoder: Mutex::new(decoder),
             tokenizer,
             mel_filters,
             device,
+            dtype,
             config,
         })
     }
 
-    pub fn start(self: Arc<Self>, rx: Receiver<Vec<f32>>, tx: Sender<TranscriptionResult>) {
+    /// Transcribe audio from `rx` live, sending results to `tx`
+    ///
+    /// The thread ends when the session handle is stopped/dropped, when `tx`'s
+    /// receiver is gone, or after a final decode once `rx` is disconnected.
+    pub fn start(self: Arc<Self>, rx: Receiver<Vec<f32>>, tx: Sender<TranscriptionResult>, options: TranscribeOptions) -> TranscriptionSession {
+        let stop = Arc::new(AtomicBool::new(false));
+        let session = TranscriptionSession { stop: stop.clone() };
         thread::spawn(move || {
             let mut audio_buffer: Vec<f32> = Vec::new();
             let sample_rate = m::SAMPLE_RATE as usize; // 16000
-            let window_size = sample_rate * 30; // 30 seconds
+            let context_secs = match options.context_secs {
+                0 => 30,
+                secs => secs.clamp(5, 30), // Whisper never sees more than 30s
+            } as usize;
+            let poll = std::time::Duration::from_millis(match options.poll_ms {
+                0 => 200,
+                ms => ms.clamp(20, 2000),
+            });
+            let window_size = sample_rate * context_secs; // Whole window is finalized once full
             let step_size = sample_rate * 3; // 3 seconds (process every 3s of new audio? No, process frequently)
             
             // For real-time, we want to process every ~0.5s, but look at context.
             // Simplified: Accumulate. When we have > 1s, transcribe.
-            // To prevent infinite buffer, keep last 30s.
+            // Once the buffer holds the full context its text is final and the buffer restarts.
+            //
+            // Confirmation (LocalAgreement): words that two consecutive
+            // hypotheses of the same buffer agree on are confirmed, the rest
+            // stays pending. `confirmed` only ever grows during a session.
+            let mut finalized = String::new();  // Text of completed 30s windows
+            let mut agreed = String::new();     // Confirmed prefix of the current window
+            let mut previous = String::new();   // Last hypothesis for the current window
+            
+            // The first decode waits for more context so it isn't based on a fragment
+            let warmup_size = ((options.warmup_secs.max(0.0) * sample_rate as f32) as usize).max(sample_rate);
+            let mut warmed_up = false;
+            let mut decoded_len = 0; // Buffer length at the last decode, to skip repeats
+            // Samples in windows that were already finalized (or skipped by a seek)
+            let mut window_offset = (options.start_secs.max(0.0) * sample_rate as f64) as usize;
             
-            loop {
+            while !stop.load(Ordering::Relaxed) {
                 // Non-blocking drain
-                while let Ok(chunk) = rx.try_recv() {
-                    audio_buffer.extend_from_slice(&chunk);
-                    log::debug!("Received audio chunk, buffer now {} samples", audio_buffer.len());
+                let mut input_closed = false;
+                let mut flush = false;
+                loop {
+                    match rx.try_recv() {
+                        // An empty chunk marks a break in the input (mic paused):
+                        // what was heard so far is finalized
+                        Ok(chunk) if chunk.is_empty() => flush = true,
+                        Ok(chunk) => {
+                            audio_buffer.extend_from_slice(&chunk);
+                            log::debug!("Received audio chunk, buffer now {} samples", audio_buffer.len());
+                        }
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            input_closed = true;
+                            break;
+                        }
+                    }
                 }
 
-                // If we have enough data to be worth transcribing (> 1s)
-                if audio_buffer.len() > sample_rate {
-                    // Cap buffer at 30s
-                    if audio_buffer.len() > window_size {
-                        let excess = audio_buffer.len() - window_size;
-                        audio_buffer.drain(0..excess);
-                    }
+                // If we have enough data to be worth transcribing (> 1s, or the warmup).
+                // Once input has ended or paused, whatever is left gets one final decode.
+                let min_len = if input_closed || flush {
+                    sample_rate / 4
+                } else if warmed_up {
+                    sample_rate
+                } else {
+                    warmup_size
+                };
+                let has_new_audio = audio_buffer.len() != decoded_len;
+                let vad = options.vad_threshold > 0.0;
+                let speech_threshold = if vad { options.vad_threshold } else { crate::audio::SPEECH_RMS_THRESHOLD };
+                if vad && has_new_audio && audio_buffer.len() > min_len && previous.is_empty()
+                    && !crate::audio::contains_speech(&audio_buffer[..audio_buffer.len().min(window_size)], speech_threshold)
+                {
+                    // Only silence so far: skip the decode (Whisper hallucinates on it)
+                    // and drop all but the tail so silence doesn't pile up
+                    let keep = (VAD_KEEP_SECS * sample_rate as f32) as usize;
+                    let dropped = audio_buffer.len().saturating_sub(keep);
+                    audio_buffer.drain(..dropped);
+                    window_offset += dropped;
+                    decoded_len = audio_buffer.len();
+                } else if (has_new_audio || flush) && audio_buffer.len() > min_len {
+                    warmed_up = true;
+                    let window_len = audio_buffer.len().min(window_size);
+                    // A pause after speech ends the window early, like a full one
+                    let pause = vad
+                        && crate::audio::trailing_silence(&audio_buffer[..window_len], speech_threshold)
+                            >= (VAD_FLUSH_SECS * sample_rate as f32) as usize;
+                    let window_full = input_closed || flush || audio_buffer.len() >= window_size || pause;
 
                     // Transcribe
-                    match self.transcribe_segment(&audio_buffer) {
-                        Ok(text) => {
-                            if !text.trim().is_empty() {
-                                let _ = tx.send(TranscriptionResult {
-                                    pending: text.clone(),
-                                    confirmed: "".to_string(), // TODO: Implement confirmation logic (LocalAgreement)
-                                });
+                    let window = &audio_buffer[..window_len];
+                    let offset_secs = window_offset as f64 / sample_rate as f64;
+                    let result = self.transcribe_segments(window, &options).and_then(|segments| {
+                        if segments_text(&segments, 0.0, false).is_empty() && options.retry_empty && crate::audio::contains_speech(window, speech_threshold) {
+                            // A greedy pass can bail out early on real speech; retry hotter
+                            log::debug!("Empty result on speech, retrying from temperature {}", RETRY_TEMPERATURE);
+                            let retry = TranscribeOptions {
+                                greedy_only: false,
+                                start_temperature: options.start_temperature.max(RETRY_TEMPERATURE),
+                                ..options.clone()
+                            };
+                            self.transcribe_segments(window, &retry)
+                        } else {
+                            Ok(segments)
+                        }
+                    });
+                    match result {
+                        Ok(mut segments) => {
+                            let text = segments_text(&segments, offset_secs, options.timestamps);
+                            let hypothesis = text.trim();
+                            let pending = if window_full {
+                                // Nothing more will be heard in this window, confirm it all
+                                agreed = hypothesis.to_string();
+                                String::new()
+                            } else {
+                                let common = crate::text::common_word_prefix(&previous, hypothesis);
+                                if common.len() > agreed.len() && common.starts_with(agreed.as_str()) {
+                                    agreed = common.to_string();
+                                }
+                                crate::text::unseen_suffix(&agreed, hypothesis).trim_start().to_string()
+                            };
+                            previous = hypothesis.to_string();
+                            
+                            let mut confirmed = finalized.clone();
+                            if !confirmed.is_empty() && !agreed.is_empty() {
+                                confirmed.push(' ');
+                            }
+                            confirmed.push_str(&agreed);
+                            
+                            // Only a finished window's segments are final
+                            if window_full {
+                                segments.retain(|seg| !seg.dr.text.trim().is_empty());
+                                for seg in &mut segments {
+                                    seg.start += offset_secs;
+                                    for word in &mut seg.words {
+                                        word.1 += offset_secs;
+                                        word.2 += offset_secs;
+                                    }
+                                }
+                            } else {
+                                segments.clear();
+                            }
+                            
+                            if (!confirmed.is_empty() || !pending.is_empty())
+                                && tx.send(TranscriptionResult { pending, confirmed: confirmed.clone(), segments }).is_err()
+                            {
+                                break; // Nobody is listening any more
+                            }
+                            
+                            if window_full {
+                                finalized = confirmed;
+                                agreed.clear();
+                                previous.clear();
                             }
                         }
                         Err(e) => log::error!("Transcription error: {}", e),
                     }
+                    
+                    if window_full {
+                        audio_buffer.drain(0..window_len);
+                        window_offset += window_len;
+                        decoded_len = 0;
+                    } else {
+                        decoded_len = audio_buffer.len();
+                    }
+                }
+
+                if input_closed {
+                    // Keep going only while there is undecoded audio left
+                    if audio_buffer.len() <= sample_rate / 4 || audio_buffer.len() == decoded_len {
+                        break;
+                    }
+                    continue;
                 }
 
                 // Sleep briefly to avoid busy loop
-                thread::sleep(std::time::Duration::from_millis(200));
+                thread::sleep(poll);
             }
+            log::debug!("Transcription session ended");
         });
+        session
     }
 
-    fn transcribe_segment(&self, pcm_data: &[f32]) -> Result<String> {
+    pub fn transcribe_segment(&self, pcm_data: &[f32], options: &TranscribeOptions) -> Result<String> {
+        self.transcribe_segment_at(pcm_data, 0.0, options)
+    }
+
+    /// Transcribe audio that starts `offset_secs` into the recording
+    ///
+    /// With `options.timestamps` each segment's text is prefixed with its
+    /// start time, e.g. `[01:30] text`.
+    pub fn transcribe_segment_at(&self, pcm_data: &[f32], offset_secs: f64, options: &TranscribeOptions) -> Result<String> {
+        let segments = self.transcribe_segments(pcm_data, options)?;
+        Ok(segments_text(&segments, offset_secs, options.timestamps))
+    }
+
+    /// Transcribe audio and keep the per-segment timing and decoding scores
+    pub fn transcribe_segments(&self, pcm_data: &[f32], options: &TranscribeOptions) -> Result<Vec<Segment>> {
+        let normalized;
+        let pcm_data = if options.normalize == "peak" || options.normalize == "rms" {
+            normalized = crate::audio::normalize_level(pcm_data, &options.normalize);
+            &normalized[..]
+        } else {
+            pcm_data
+        };
         let mel = audio::pcm_to_mel(&self.config, pcm_data, &self.mel_filters);
         let mel_len = mel.len();
         log::debug!("Transcribing {} samples -> {} mel bins", pcm_data.len(), mel_len / self.config.num_mel_bins);
@@ -106,42 +375,112 @@ impl WhisperTranscriber {
             mel,
             (1, self.config.num_mel_bins, mel_len / self.config.num_mel_bins),
             &self.device,
-        )?;
+        )?.to_dtype(self.dtype)?;
 
-        // Create a new decoder for this segment
-        // We use default seed for deterministic results? Or random?
-        let mut decoder = Decoder::new(
-            match &self.model {
-                Model::Normal(m) => Model::Normal(m.clone()), // Clone wrapper, cheap for Arc weights?
-                Model::Quantized(m) => Model::Quantized(m.clone())
-            }, 
-            self.tokenizer.clone(), 
-            299792458, // Seed
-            &self.device, 
-            None, 
-            Some(Task::Transcribe), 
-            true, // Timestamps 
-            None, 
-            false // Verbose
-        )?;
+        let language_token = match options.language.as_deref() {
+            Some(code) => {
+                let token = self.language_token(code);
+                if token.is_none() {
+                    log::warn!("Unknown language '{}' for this model, auto-detecting", code);
+                }
+                token
+            }
+            None => None,
+        };
+
+        let prompt_tokens = if options.prompt.trim().is_empty() {
+            None
+        } else {
+            let encoding = self.tokenizer
+                .encode(format!(" {}", options.prompt.trim()), false)
+                .map_err(|e| anyhow!(e))?;
+            Some(encoding.get_ids().to_vec())
+        };
+
+        // Reuse the shared decoder, resetting what the previous run left behind
+        let mut decoder = self.decoder.lock().map_err(|_| anyhow!("Decoder lock poisoned"))?;
+        decoder.reset();
+        decoder.configure(language_token, Some(options.task), options.timestamps);
+        decoder.set_word_timestamps(options.word_timestamps);
+        decoder.set_greedy_only(options.greedy_only);
+        decoder.set_beam_size(options.beam_size);
+        decoder.set_max_repeats(options.max_repeats);
+        decoder.set_thresholds(options.thresholds);
+        decoder.set_start_temperature(options.start_temperature);
+        if let Some(tokens) = prompt_tokens {
+            decoder.set_prompt(tokens);
+        }
 
-        let segments = match decoder.run(&mel_tensor) {
-            Ok(segs) => segs,
+        match decoder.run(&mel_tensor) {
+            Ok(segs) => Ok(segs),
             Err(e) => {
                 log::error!("Decoder run failed: {:?}", e);
-                return Err(e.into());
+                Err(e)
             }
-        };
-        
-        let mut full_text = String::new();
-        for seg in segments {
-            full_text.push_str(&seg.dr.text);
-            full_text.push(' ');
         }
-        
-        Ok(full_text.trim().to_string())
     }
+    /// Token for a language code like "es", if this model knows the language
+    pub fn language_token(&self, code: &str) -> Option<u32> {
+        if !self.is_multilingual() {
+            return None;
+        }
+        decoder::token_id(&self.tokenizer, &format!("<|{}|>", code.trim().to_lowercase())).ok()
+    }
+
+    pub fn is_multilingual(&self) -> bool {
+        decoder::is_multilingual(&self.config)
+    }
+
     pub fn get_device_name(&self) -> String {
-        format!("{:?}", self.device)
+        format!("{:?} {:?}", self.device, self.dtype)
+    }
+}
+
+/// Open the compute device named in the settings. "auto" prefers a GPU and
+/// falls back to the CPU; an explicit GPU backend fails if it can't be used.
+fn resolve_device(device: &str) -> Result<Device> {
+    match device {
+        "cpu" => Ok(Device::Cpu),
+        "cuda" if !candle::utils::cuda_is_available() => {
+            bail!("CUDA support isn't built in; rebuild with `--features cuda` or select CPU")
+        }
+        "cuda" => Device::new_cuda(0).map_err(|e| anyhow!("CUDA device unavailable: {}", e)),
+        "metal" if !candle::utils::metal_is_available() => {
+            bail!("Metal support isn't built in; rebuild with `--features metal` or select CPU")
+        }
+        "metal" => Device::new_metal(0).map_err(|e| anyhow!("Metal device unavailable: {}", e)),
+        _ => Ok(Device::new_cuda(0)
+            .or_else(|_| Device::new_metal(0))
+            .unwrap_or(Device::Cpu)),
+    }
+}
+
+/// Pick the weight precision for a device, rejecting combinations it can't run
+fn resolve_dtype(precision: &str, device: &Device) -> Result<DType> {
+    match precision {
+        "f32" => Ok(DType::F32),
+        "f16" if device.is_cpu() => bail!("F16 precision needs a GPU; select F32 or Auto on CPU"),
+        "f16" => Ok(DType::F16),
+        // Auto: half precision is faster on GPU, CPU kernels need full precision
+        _ => Ok(if device.is_cpu() { m::DTYPE } else { DType::F16 }),
+    }
+}
+
+/// Join segment texts, prefixing each with `[mm:ss] ` (counted from
+/// `offset_secs`) when `timestamps` is set
+pub fn segments_text(segments: &[Segment], offset_secs: f64, timestamps: bool) -> String {
+    let mut full_text = String::new();
+    for seg in segments {
+        let text = seg.dr.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        if timestamps {
+            let secs = (offset_secs + seg.start) as u64;
+            full_text.push_str(&format!("[{:02}:{:02}] ", secs / 60, secs % 60));
+        }
+        full_text.push_str(text);
+        full_text.push(' ');
     }
+    full_text.trim().to_string()
 }
@@ -0,0 +1,479 @@
+//! Whisper encoder and decoder, ported from candle-transformers 0.8.4
+//! (`models::whisper::model` and `quantized_model`, MIT/Apache-2.0)
+//!
+//! Candle keeps the attention weights private. This copy also returns the
+//! decoder's cross-attention scores, which word-level timestamps align
+//! against the audio, and loads both safetensors and quantized GGUF weights
+//! into the same model.
+
+use candle_core::{DType, Device, IndexOp, Module, Result, Shape, Tensor, D};
+use candle_nn::{Conv1d, Conv1dConfig, Embedding, LayerNorm};
+use candle_transformers::models::whisper::Config;
+use candle_transformers::{quantized_nn, quantized_var_builder};
+
+/// Where the weights come from: a safetensors file or a quantized GGUF file
+/// (dequantized except for the linear layers)
+#[derive(Clone)]
+pub enum Weights<'a> {
+    Full(candle_nn::VarBuilder<'a>),
+    Quantized(quantized_var_builder::VarBuilder),
+}
+
+impl Weights<'_> {
+    fn pp(&self, s: impl ToString) -> Self {
+        match self {
+            Self::Full(vb) => Self::Full(vb.pp(s)),
+            Self::Quantized(vb) => Self::Quantized(vb.pp(s)),
+        }
+    }
+
+    fn device(&self) -> &Device {
+        match self {
+            Self::Full(vb) => vb.device(),
+            Self::Quantized(vb) => vb.device(),
+        }
+    }
+
+    /// Type of the activations: the weights' type, f32 for dequantized weights
+    fn dtype(&self) -> DType {
+        match self {
+            Self::Full(vb) => vb.dtype(),
+            Self::Quantized(_) => DType::F32,
+        }
+    }
+
+    fn get(&self, shape: impl Into<Shape>, name: &str) -> Result<Tensor> {
+        match self {
+            Self::Full(vb) => vb.get(shape, name),
+            Self::Quantized(vb) => vb.get(shape, name)?.dequantize(vb.device()),
+        }
+    }
+
+    fn linear(&self, in_dim: usize, out_dim: usize, bias: bool) -> Result<Linear> {
+        match (self, bias) {
+            (Self::Full(vb), true) => candle_nn::linear(in_dim, out_dim, vb.clone()).map(Linear::Full),
+            (Self::Full(vb), false) => candle_nn::linear_no_bias(in_dim, out_dim, vb.clone()).map(Linear::Full),
+            (Self::Quantized(vb), true) => quantized_nn::linear(in_dim, out_dim, vb.clone()).map(Linear::Quantized),
+            (Self::Quantized(vb), false) => quantized_nn::linear_no_bias(in_dim, out_dim, vb.clone()).map(Linear::Quantized),
+        }
+    }
+
+    fn layer_norm(&self, size: usize) -> Result<LayerNorm> {
+        Ok(LayerNorm::new(self.get(size, "weight")?, self.get(size, "bias")?, 1e-5))
+    }
+
+    fn conv1d(&self, in_channels: usize, out_channels: usize, kernel_size: usize, config: Conv1dConfig) -> Result<Conv1d> {
+        let weight = self.get((out_channels, in_channels, kernel_size), "weight")?;
+        let bias = self.get(out_channels, "bias")?;
+        Ok(Conv1d::new(weight, Some(bias), config))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Linear {
+    Full(candle_nn::Linear),
+    Quantized(quantized_nn::Linear),
+}
+
+impl Module for Linear {
+    fn forward(&self, x: &Tensor) -> Result<Tensor> {
+        match self {
+            Self::Full(l) => l.forward(x),
+            Self::Quantized(l) => l.forward(x),
+        }
+    }
+}
+
+// https://github.com/openai/whisper/blob/f572f2161ba831bae131364c3bffdead7af6d210/whisper/model.py#L62
+#[derive(Debug, Clone)]
+struct MultiHeadAttention {
+    query: Linear,
+    key: Linear,
+    value: Linear,
+    out: Linear,
+    n_head: usize,
+    kv_cache: Option<(Tensor, Tensor)>,
+}
+
+impl MultiHeadAttention {
+    fn load(n_state: usize, n_head: usize, vb: Weights) -> Result<Self> {
+        Ok(Self {
+            query: vb.pp("q_proj").linear(n_state, n_state, true)?,
+            key: vb.pp("k_proj").linear(n_state, n_state, false)?,
+            value: vb.pp("v_proj").linear(n_state, n_state, true)?,
+            out: vb.pp("out_proj").linear(n_state, n_state, true)?,
+            n_head,
+            kv_cache: None,
+        })
+    }
+
+    /// Attend and also return the attention scores before softmax,
+    /// shaped (batch, heads, queries, keys)
+    fn forward(
+        &mut self,
+        x: &Tensor,
+        xa: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        flush_cache: bool,
+    ) -> Result<(Tensor, Tensor)> {
+        let q = self.query.forward(x)?;
+        let (k, v) = match xa {
+            None => {
+                let k = self.key.forward(x)?;
+                let v = self.value.forward(x)?;
+                (k, v)
+            }
+            Some(x) => {
+                if flush_cache {
+                    self.kv_cache = None;
+                }
+                if let Some((k, v)) = &self.kv_cache {
+                    (k.clone(), v.clone())
+                } else {
+                    let k = self.key.forward(x)?;
+                    let v = self.value.forward(x)?;
+                    self.kv_cache = Some((k.clone(), v.clone()));
+                    (k, v)
+                }
+            }
+        };
+        let (wv, qk) = self.qkv_attention(&q, &k, &v, mask)?;
+        let out = self.out.forward(&wv)?;
+        Ok((out, qk))
+    }
+
+    fn reshape_head(&self, x: &Tensor) -> Result<Tensor> {
+        let (n_batch, n_ctx, n_state) = x.dims3()?;
+        let target_dims = &[n_batch, n_ctx, self.n_head, n_state / self.n_head];
+        x.reshape(target_dims)?.transpose(1, 2)
+    }
+
+    fn qkv_attention(
+        &self,
+        q: &Tensor,
+        k: &Tensor,
+        v: &Tensor,
+        mask: Option<&Tensor>,
+    ) -> Result<(Tensor, Tensor)> {
+        let (_, n_ctx, n_state) = q.dims3()?;
+        let scale = ((n_state / self.n_head) as f64).powf(-0.25);
+        let q = (self.reshape_head(q)? * scale)?;
+        let k = (self.reshape_head(k)?.transpose(2, 3)? * scale)?;
+        let v = self.reshape_head(v)?.contiguous()?;
+        let mut qk = q.matmul(&k)?;
+        if let Some(mask) = mask {
+            let mask = mask.i((0..n_ctx, 0..n_ctx))?;
+            qk = qk.broadcast_add(&mask)?
+        }
+        let w = candle_nn::ops::softmax_last_dim(&qk)?;
+        let wv = w.matmul(&v)?.transpose(1, 2)?.flatten_from(2)?;
+        Ok((wv, qk))
+    }
+
+    fn reset_kv_cache(&mut self) {
+        self.kv_cache = None;
+    }
+}
+
+// https://github.com/openai/whisper/blob/f572f2161ba831bae131364c3bffdead7af6d210/whisper/model.py#L111
+#[derive(Debug, Clone)]
+struct ResidualAttentionBlock {
+    attn: MultiHeadAttention,
+    attn_ln: LayerNorm,
+    cross_attn: Option<(MultiHeadAttention, LayerNorm)>,
+    mlp_linear1: Linear,
+    mlp_linear2: Linear,
+    mlp_ln: LayerNorm,
+}
+
+impl ResidualAttentionBlock {
+    fn load(n_state: usize, n_head: usize, ca: bool, vb: Weights) -> Result<Self> {
+        let attn = MultiHeadAttention::load(n_state, n_head, vb.pp("self_attn"))?;
+        let attn_ln = vb.pp("self_attn_layer_norm").layer_norm(n_state)?;
+        let cross_attn = if ca {
+            let cross_attn = MultiHeadAttention::load(n_state, n_head, vb.pp("encoder_attn"))?;
+            let cross_attn_ln = vb.pp("encoder_attn_layer_norm").layer_norm(n_state)?;
+            Some((cross_attn, cross_attn_ln))
+        } else {
+            None
+        };
+        let n_mlp = n_state * 4;
+        let mlp_linear1 = vb.pp("fc1").linear(n_state, n_mlp, true)?;
+        let mlp_linear2 = vb.pp("fc2").linear(n_mlp, n_state, true)?;
+        let mlp_ln = vb.pp("final_layer_norm").layer_norm(n_state)?;
+        Ok(Self {
+            attn,
+            attn_ln,
+            cross_attn,
+            mlp_linear1,
+            mlp_linear2,
+            mlp_ln,
+        })
+    }
+
+    /// Run the block, also returning its cross-attention scores if it has any
+    fn forward(
+        &mut self,
+        x: &Tensor,
+        xa: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        flush_kv_cache: bool,
+    ) -> Result<(Tensor, Option<Tensor>)> {
+        let (attn, _) = self
+            .attn
+            .forward(&self.attn_ln.forward(x)?, None, mask, flush_kv_cache)?;
+        let mut x = (x + attn)?;
+        let mut cross_qk = None;
+        if let Some((attn, ln)) = &mut self.cross_attn {
+            let (cross, qk) = attn.forward(&ln.forward(&x)?, xa, None, flush_kv_cache)?;
+            x = (&x + cross)?;
+            cross_qk = Some(qk);
+        }
+        let mlp = self.mlp_linear2.forward(
+            &self
+                .mlp_linear1
+                .forward(&self.mlp_ln.forward(&x)?)?
+                .gelu()?,
+        )?;
+        Ok(((x + mlp)?, cross_qk))
+    }
+
+    fn reset_kv_cache(&mut self) {
+        self.attn.reset_kv_cache();
+        if let Some((attn, _)) = &mut self.cross_attn {
+            attn.reset_kv_cache();
+        }
+    }
+}
+
+fn sinusoids(length: usize, channels: usize, device: &Device) -> Result<Tensor> {
+    let max_timescale = 10000f32;
+    let log_timescale_increment = max_timescale.ln() / (channels / 2 - 1) as f32;
+    let inv_timescales: Vec<_> = (0..channels / 2)
+        .map(|i| (i as f32 * (-log_timescale_increment)).exp())
+        .collect();
+    let inv_timescales = Tensor::new(inv_timescales.as_slice(), device)?.unsqueeze(0)?;
+    let arange = Tensor::arange(0, length as u32, device)?
+        .to_dtype(DType::F32)?
+        .unsqueeze(1)?;
+    let sh = (length, channels / 2);
+    let scaled_time = (arange.broadcast_as(sh)? * inv_timescales.broadcast_as(sh)?)?;
+    let sincos = Tensor::cat(&[scaled_time.sin()?, scaled_time.cos()?], 1)?;
+    Ok(sincos)
+}
+
+// https://github.com/openai/whisper/blob/f572f2161ba831bae131364c3bffdead7af6d210/whisper/model.py#L143
+#[derive(Debug, Clone)]
+pub struct AudioEncoder {
+    conv1: Conv1d,
+    conv2: Conv1d,
+    positional_embedding: Tensor,
+    blocks: Vec<ResidualAttentionBlock>,
+    ln_post: LayerNorm,
+}
+
+impl AudioEncoder {
+    fn load(vb: Weights, cfg: &Config) -> Result<Self> {
+        let n_state = cfg.d_model;
+        let n_head = cfg.encoder_attention_heads;
+        let n_ctx = cfg.max_source_positions;
+        let cfg1 = Conv1dConfig {
+            padding: 1,
+            stride: 1,
+            groups: 1,
+            dilation: 1,
+        };
+        let cfg2 = Conv1dConfig {
+            padding: 1,
+            stride: 2,
+            groups: 1,
+            dilation: 1,
+        };
+        let conv1 = vb.pp("conv1").conv1d(cfg.num_mel_bins, n_state, 3, cfg1)?;
+        let conv2 = vb.pp("conv2").conv1d(n_state, n_state, 3, cfg2)?;
+        // Added to the activations, so it must match the weights' precision
+        let positional_embedding = sinusoids(n_ctx, n_state, vb.device())?.to_dtype(vb.dtype())?;
+        let blocks = (0..cfg.encoder_layers)
+            .map(|i| {
+                ResidualAttentionBlock::load(n_state, n_head, false, vb.pp(format!("layers.{i}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let ln_post = vb.pp("layer_norm").layer_norm(n_state)?;
+        Ok(Self {
+            conv1,
+            conv2,
+            positional_embedding,
+            blocks,
+            ln_post,
+        })
+    }
+
+    pub fn forward(&mut self, x: &Tensor, flush_kv_cache: bool) -> Result<Tensor> {
+        let x = self.conv1.forward(x)?.gelu()?;
+        let x = self.conv2.forward(&x)?.gelu()?;
+        let x = x.transpose(1, 2)?;
+        let (_bsize, seq_len, _hidden) = x.dims3()?;
+        let positional_embedding = self.positional_embedding.narrow(0, 0, seq_len)?;
+        let mut x = x.broadcast_add(&positional_embedding)?;
+        for block in self.blocks.iter_mut() {
+            x = block.forward(&x, None, None, flush_kv_cache)?.0
+        }
+        let x = self.ln_post.forward(&x)?;
+        Ok(x)
+    }
+
+    pub fn reset_kv_cache(&mut self) {
+        for block in self.blocks.iter_mut() {
+            block.reset_kv_cache();
+        }
+    }
+}
+
+// https://github.com/openai/whisper/blob/f572f2161ba831bae131364c3bffdead7af6d210/whisper/model.py#L176
+#[derive(Debug, Clone)]
+pub struct TextDecoder {
+    token_embedding: Embedding,
+    positional_embedding: Tensor,
+    blocks: Vec<ResidualAttentionBlock>,
+    ln: LayerNorm,
+    mask: Tensor,
+}
+
+impl TextDecoder {
+    fn load(vb: Weights, cfg: &Config) -> Result<Self> {
+        let n_state = cfg.d_model;
+        let n_head = cfg.decoder_attention_heads;
+        let n_ctx = cfg.max_target_positions;
+        let embeddings = vb.pp("embed_tokens").get((cfg.vocab_size, n_state), "weight")?;
+        let token_embedding = Embedding::new(embeddings, n_state);
+        let positional_embedding = vb.get((n_ctx, n_state), "embed_positions.weight")?;
+        let blocks = (0..cfg.decoder_layers)
+            .map(|i| {
+                ResidualAttentionBlock::load(n_state, n_head, true, vb.pp(format!("layers.{i}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let ln = vb.pp("layer_norm").layer_norm(n_state)?;
+        let mask: Vec<_> = (0..n_ctx)
+            .flat_map(|i| (0..n_ctx).map(move |j| if j > i { f32::NEG_INFINITY } else { 0f32 }))
+            .collect();
+        let mask = Tensor::from_vec(mask, (n_ctx, n_ctx), vb.device())?.to_dtype(vb.dtype())?;
+        Ok(Self {
+            token_embedding,
+            positional_embedding,
+            blocks,
+            ln,
+            mask,
+        })
+    }
+
+    pub fn forward(&mut self, x: &Tensor, xa: &Tensor, flush_kv_cache: bool) -> Result<Tensor> {
+        Ok(self.forward_with_cross_attention(x, xa, flush_kv_cache)?.0)
+    }
+
+    /// Like `forward`, also returning each layer's cross-attention scores
+    /// before softmax, shaped (batch, heads, tokens, audio frames)
+    pub fn forward_with_cross_attention(
+        &mut self,
+        x: &Tensor,
+        xa: &Tensor,
+        flush_kv_cache: bool,
+    ) -> Result<(Tensor, Vec<Tensor>)> {
+        let last = x.dim(D::Minus1)?;
+        let token_embedding = self.token_embedding.forward(x)?;
+        let positional_embedding = self.positional_embedding.narrow(0, 0, last)?;
+        let mut x = token_embedding.broadcast_add(&positional_embedding)?;
+        let mut cross_qk = Vec::with_capacity(self.blocks.len());
+        for block in self.blocks.iter_mut() {
+            let (out, qk) = block.forward(&x, Some(xa), Some(&self.mask), flush_kv_cache)?;
+            x = out;
+            cross_qk.extend(qk);
+        }
+        Ok((self.ln.forward(&x)?, cross_qk))
+    }
+
+    pub fn final_linear(&self, x: &Tensor) -> Result<Tensor> {
+        let b_size = x.dim(0)?;
+        let w = self.token_embedding.embeddings().broadcast_left(b_size)?;
+        x.matmul(&w.t()?)
+    }
+
+    pub fn reset_kv_cache(&mut self) {
+        for block in self.blocks.iter_mut() {
+            block.reset_kv_cache();
+        }
+    }
+}
+
+// https://github.com/openai/whisper/blob/f572f2161ba831bae131364c3bffdead7af6d210/whisper/model.py#L221
+#[derive(Debug, Clone)]
+pub struct Whisper {
+    pub encoder: AudioEncoder,
+    pub decoder: TextDecoder,
+    pub config: Config,
+}
+
+impl Whisper {
+    pub fn load(vb: &Weights, config: Config) -> Result<Self> {
+        let encoder = AudioEncoder::load(vb.pp("model.encoder"), &config)?;
+        let decoder = TextDecoder::load(vb.pp("model.decoder"), &config)?;
+        Ok(Self {
+            encoder,
+            decoder,
+            config,
+        })
+    }
+
+    pub fn reset_kv_cache(&mut self) {
+        self.encoder.reset_kv_cache();
+        self.decoder.reset_kv_cache();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candle_nn::{VarBuilder, VarMap};
+
+    /// Whisper with tiny.en's layout at a fraction of its width, random weights
+    fn random_model(dtype: DType) -> Whisper {
+        let config = Config {
+            num_mel_bins: 80,
+            max_source_positions: 50,
+            d_model: 64,
+            encoder_attention_heads: 2,
+            encoder_layers: 2,
+            vocab_size: 100,
+            max_target_positions: 16,
+            decoder_attention_heads: 2,
+            decoder_layers: 2,
+            suppress_tokens: Vec::new(),
+        };
+        let varmap = VarMap::new();
+        let weights = Weights::Full(VarBuilder::from_varmap(&varmap, dtype, &Device::Cpu));
+        Whisper::load(&weights, config.clone()).unwrap();
+        for var in varmap.all_vars() {
+            let random = Tensor::randn(0f32, 0.1, var.shape(), &Device::Cpu).unwrap();
+            var.set(&random.to_dtype(dtype).unwrap()).unwrap();
+        }
+        Whisper::load(&weights, config).unwrap()
+    }
+
+    #[test]
+    fn half_precision_model_runs() {
+        let mut model = random_model(DType::F16);
+        let mel = Tensor::randn(0f32, 1.0, (1, 80, 100), &Device::Cpu)
+            .unwrap()
+            .to_dtype(DType::F16)
+            .unwrap();
+        let audio = model.encoder.forward(&mel, true).unwrap();
+        assert_eq!(audio.dims(), [1, 50, 64]);
+        assert_eq!(audio.dtype(), DType::F16);
+
+        let tokens = Tensor::new(&[[1u32, 2, 3]], &Device::Cpu).unwrap();
+        let hidden = model.decoder.forward(&tokens, &audio, true).unwrap();
+        let logits = model.decoder.final_linear(&hidden).unwrap();
+        assert_eq!(logits.dims(), [1, 3, 100]);
+        let logits: Vec<f32> = logits.to_dtype(DType::F32).unwrap().flatten_all().unwrap().to_vec1().unwrap();
+        assert!(logits.iter().all(|x| x.is_finite()));
+    }
+}
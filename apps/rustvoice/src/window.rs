@@ -0,0 +1,354 @@
+//! Target window enumeration and focus management
+//!
+//! Lists the visible top-level windows so the user can pick the application
+//! that should receive typed text, and brings that window to the foreground
+//! right before typing starts. Also reports the monitor layout so a saved
+//! window position can be checked before it is restored.
+
+use anyhow::Result;
+
+/// A top-level window that can receive typed text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowInfo {
+    pub id: u64,
+    pub title: String,
+}
+
+/// A monitor's area on the desktop, in logical points like the saved
+/// window position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonitorRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl MonitorRect {
+    /// Convert a rectangle in pixels to points, `scale` pixels per point
+    fn from_pixels(x: f32, y: f32, width: f32, height: f32, scale: f32) -> Self {
+        let scale = if scale > 0.0 { scale } else { 1.0 };
+        Self { x: x / scale, y: y / scale, width: width / scale, height: height / scale }
+    }
+}
+
+/// How much of the window's top edge must stay on a monitor to be reachable
+const VISIBLE_MARGIN: f32 = 48.0;
+
+/// Get list of visible, titled top-level windows (our own window excluded)
+pub fn list_windows() -> Vec<WindowInfo> {
+    let mut windows = match platform::list_windows() {
+        Ok(w) => w,
+        Err(e) => {
+            log::warn!("Window enumeration failed: {}", e);
+            Vec::new()
+        }
+    };
+    windows.retain(|w| !w.title.trim().is_empty() && w.title != "rustVoice");
+    windows.sort_by_key(|w| w.title.to_lowercase());
+    windows
+}
+
+/// Bring a window to the foreground
+pub fn focus_window(id: u64) -> Result<()> {
+    platform::focus_window(id)
+}
+
+/// Get the id of the window that currently has focus
+pub fn foreground_window() -> Option<u64> {
+    platform::foreground_window()
+}
+
+/// Areas of the connected monitors (empty if they can't be queried)
+pub fn monitor_rects() -> Vec<MonitorRect> {
+    platform::monitor_rects().unwrap_or_else(|e| {
+        log::warn!("Monitor enumeration failed: {}", e);
+        Vec::new()
+    })
+}
+
+/// Move a saved window position back onto a monitor when its title bar would
+/// be off every connected one, e.g. after unplugging a second screen. The
+/// window is centered on the first monitor. Positions are left alone when
+/// the monitors are unknown.
+pub fn clamp_to_monitors(pos: [f32; 2], size: [f32; 2], monitors: &[MonitorRect]) -> [f32; 2] {
+    let reachable = |m: &MonitorRect| {
+        pos[0] + size[0] - VISIBLE_MARGIN >= m.x
+            && pos[0] + VISIBLE_MARGIN <= m.x + m.width
+            && pos[1] >= m.y - VISIBLE_MARGIN
+            && pos[1] + VISIBLE_MARGIN <= m.y + m.height
+    };
+    match monitors.first() {
+        Some(first) if !monitors.iter().any(reachable) => [
+            first.x + ((first.width - size[0]) / 2.0).max(0.0),
+            first.y + ((first.height - size[1]) / 2.0).max(0.0),
+        ],
+        _ => pos,
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{MonitorRect, WindowInfo};
+    use anyhow::{bail, Result};
+    use windows_sys::Win32::Foundation::{BOOL, HWND, LPARAM, RECT};
+    use windows_sys::Win32::Graphics::Gdi::{EnumDisplayMonitors, HDC, HMONITOR};
+    use windows_sys::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetForegroundWindow, GetWindowTextLengthW, GetWindowTextW, IsIconic,
+        IsWindowVisible, SetForegroundWindow, ShowWindow, SW_RESTORE,
+    };
+
+    unsafe extern "system" fn enum_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let windows = &mut *(lparam as *mut Vec<WindowInfo>);
+        if IsWindowVisible(hwnd) == 0 {
+            return 1;
+        }
+        let len = GetWindowTextLengthW(hwnd);
+        if len > 0 {
+            let mut buf = vec![0u16; len as usize + 1];
+            let copied = GetWindowTextW(hwnd, buf.as_mut_ptr(), buf.len() as i32);
+            if copied > 0 {
+                windows.push(WindowInfo {
+                    id: hwnd as u64,
+                    title: String::from_utf16_lossy(&buf[..copied as usize]),
+                });
+            }
+        }
+        1 // Continue enumeration
+    }
+
+    pub fn list_windows() -> Result<Vec<WindowInfo>> {
+        let mut windows: Vec<WindowInfo> = Vec::new();
+        unsafe {
+            EnumWindows(Some(enum_proc), &mut windows as *mut Vec<WindowInfo> as LPARAM);
+        }
+        Ok(windows)
+    }
+
+    pub fn focus_window(id: u64) -> Result<()> {
+        let hwnd = id as HWND;
+        unsafe {
+            if IsIconic(hwnd) != 0 {
+                ShowWindow(hwnd, SW_RESTORE);
+            }
+            if SetForegroundWindow(hwnd) == 0 {
+                bail!("SetForegroundWindow was refused");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn foreground_window() -> Option<u64> {
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd.is_null() {
+            None
+        } else {
+            Some(hwnd as u64)
+        }
+    }
+
+    unsafe extern "system" fn monitor_proc(monitor: HMONITOR, _hdc: HDC, rect: *mut RECT, lparam: LPARAM) -> BOOL {
+        let monitors = &mut *(lparam as *mut Vec<MonitorRect>);
+        let r = &*rect;
+        // Each monitor has its own scale; 96 DPI is one pixel per point
+        let (mut dpi_x, mut dpi_y) = (96, 96);
+        if GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) != 0 {
+            dpi_x = 96;
+        }
+        monitors.push(MonitorRect::from_pixels(
+            r.left as f32,
+            r.top as f32,
+            (r.right - r.left) as f32,
+            (r.bottom - r.top) as f32,
+            dpi_x as f32 / 96.0,
+        ));
+        1 // Continue enumeration
+    }
+
+    pub fn monitor_rects() -> Result<Vec<MonitorRect>> {
+        let mut monitors: Vec<MonitorRect> = Vec::new();
+        let ok = unsafe {
+            EnumDisplayMonitors(
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                Some(monitor_proc),
+                &mut monitors as *mut Vec<MonitorRect> as LPARAM,
+            )
+        };
+        if ok == 0 {
+            bail!("EnumDisplayMonitors failed");
+        }
+        Ok(monitors)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    //! X11 implementation using the EWMH root window properties.
+    //! Wayland sessions do not expose other clients' windows, so
+    //! enumeration simply fails there and the picker stays empty.
+
+    use super::{MonitorRect, WindowInfo};
+    use anyhow::{anyhow, Result};
+    use x11rb::connection::Connection;
+    use x11rb::protocol::randr::ConnectionExt as _;
+    use x11rb::protocol::xproto::{
+        Atom, AtomEnum, ClientMessageEvent, ConnectionExt, EventMask, Window,
+    };
+    use x11rb::rust_connection::RustConnection;
+
+    fn connect() -> Result<(RustConnection, Window)> {
+        let (conn, screen_num) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen_num].root;
+        Ok((conn, root))
+    }
+
+    fn atom(conn: &RustConnection, name: &[u8]) -> Result<Atom> {
+        Ok(conn.intern_atom(false, name)?.reply()?.atom)
+    }
+
+    fn window_title(
+        conn: &RustConnection,
+        window: Window,
+        net_wm_name: Atom,
+        utf8_string: Atom,
+    ) -> Result<String> {
+        let reply = conn
+            .get_property(false, window, net_wm_name, utf8_string, 0, 1024)?
+            .reply()?;
+        if !reply.value.is_empty() {
+            return Ok(String::from_utf8_lossy(&reply.value).into_owned());
+        }
+        // Fall back to the legacy ICCCM name
+        let reply = conn
+            .get_property(false, window, AtomEnum::WM_NAME, AtomEnum::STRING, 0, 1024)?
+            .reply()?;
+        Ok(String::from_utf8_lossy(&reply.value).into_owned())
+    }
+
+    pub fn list_windows() -> Result<Vec<WindowInfo>> {
+        let (conn, root) = connect()?;
+        let client_list = atom(&conn, b"_NET_CLIENT_LIST")?;
+        let reply = conn
+            .get_property(false, root, client_list, AtomEnum::WINDOW, 0, u32::MAX)?
+            .reply()?;
+        let ids = reply
+            .value32()
+            .ok_or_else(|| anyhow!("_NET_CLIENT_LIST not supported by window manager"))?;
+
+        let net_wm_name = atom(&conn, b"_NET_WM_NAME")?;
+        let utf8_string = atom(&conn, b"UTF8_STRING")?;
+        let mut windows = Vec::new();
+        for id in ids {
+            if let Ok(title) = window_title(&conn, id, net_wm_name, utf8_string) {
+                windows.push(WindowInfo { id: id as u64, title });
+            }
+        }
+        Ok(windows)
+    }
+
+    pub fn focus_window(id: u64) -> Result<()> {
+        let (conn, root) = connect()?;
+        let active_window = atom(&conn, b"_NET_ACTIVE_WINDOW")?;
+        // Source indication 2 = pager, which window managers honour without
+        // focus-stealing prevention kicking in.
+        let event = ClientMessageEvent::new(32, id as Window, active_window, [2, 0, 0, 0, 0]);
+        conn.send_event(
+            false,
+            root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event,
+        )?;
+        conn.flush()?;
+        Ok(())
+    }
+
+    pub fn foreground_window() -> Option<u64> {
+        let (conn, root) = connect().ok()?;
+        let active_window = atom(&conn, b"_NET_ACTIVE_WINDOW").ok()?;
+        let reply = conn
+            .get_property(false, root, active_window, AtomEnum::WINDOW, 0, 1)
+            .ok()?
+            .reply()
+            .ok()?;
+        let id = reply.value32()?.next()?;
+        Some(id as u64)
+    }
+
+    /// `Xft.dpi` from the X resource database, if set
+    fn xft_dpi(conn: &RustConnection, root: Window) -> Option<f32> {
+        let reply = conn
+            .get_property(false, root, AtomEnum::RESOURCE_MANAGER, AtomEnum::STRING, 0, u32::MAX / 4)
+            .ok()?
+            .reply()
+            .ok()?;
+        let resources = String::from_utf8_lossy(&reply.value);
+        resources
+            .lines()
+            .find_map(|line| line.strip_prefix("Xft.dpi:"))
+            .and_then(|dpi| dpi.trim().parse().ok())
+    }
+
+    /// Pixels per point of a monitor, chosen like winit does: the
+    /// `WINIT_X11_SCALE_FACTOR` override, then `Xft.dpi`, then the
+    /// monitor's physical size (in 1/12 steps, never below 1)
+    fn scale_factor(xft_dpi: Option<f32>, size_px: (u16, u16), size_mm: (u32, u32)) -> f32 {
+        let from_size = || {
+            if size_mm.0 == 0 || size_mm.1 == 0 {
+                return 1.0;
+            }
+            let px_per_mm = ((size_px.0 as f32 * size_px.1 as f32) / (size_mm.0 as f32 * size_mm.1 as f32)).sqrt();
+            let scale = ((px_per_mm * (12.0 * 25.4 / 96.0)).round() / 12.0).max(1.0);
+            if scale <= 20.0 { scale } else { 1.0 }
+        };
+        match std::env::var("WINIT_X11_SCALE_FACTOR").ok().filter(|v| !v.is_empty()) {
+            Some(v) if v.eq_ignore_ascii_case("randr") => from_size(),
+            Some(v) => v.parse().unwrap_or(1.0),
+            None => xft_dpi.map(|dpi| dpi / 96.0).unwrap_or_else(from_size),
+        }
+    }
+
+    pub fn monitor_rects() -> Result<Vec<MonitorRect>> {
+        let (conn, root) = connect()?;
+        let xft_dpi = xft_dpi(&conn, root);
+        // RandR 1.5 lists monitors; without it the root window spans them all
+        let reply = conn.randr_get_monitors(root, true).ok().and_then(|c| c.reply().ok());
+        if let Some(reply) = reply.filter(|r| !r.monitors.is_empty()) {
+            return Ok(reply
+                .monitors
+                .iter()
+                .map(|m| {
+                    let size_mm = (m.width_in_millimeters, m.height_in_millimeters);
+                    let scale = scale_factor(xft_dpi, (m.width, m.height), size_mm);
+                    MonitorRect::from_pixels(m.x as f32, m.y as f32, m.width as f32, m.height as f32, scale)
+                })
+                .collect());
+        }
+        let geometry = conn.get_geometry(root)?.reply()?;
+        let scale = scale_factor(xft_dpi, (geometry.width, geometry.height), (0, 0));
+        Ok(vec![MonitorRect::from_pixels(0.0, 0.0, geometry.width as f32, geometry.height as f32, scale)])
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+mod platform {
+    use super::{MonitorRect, WindowInfo};
+    use anyhow::{bail, Result};
+
+    pub fn list_windows() -> Result<Vec<WindowInfo>> {
+        bail!("Window enumeration is not supported on this platform")
+    }
+
+    pub fn focus_window(_id: u64) -> Result<()> {
+        bail!("Focusing windows is not supported on this platform")
+    }
+
+    pub fn foreground_window() -> Option<u64> {
+        None
+    }
+
+    pub fn monitor_rects() -> Result<Vec<MonitorRect>> {
+        bail!("Monitor enumeration is not supported on this platform")
+    }
+}